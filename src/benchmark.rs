@@ -0,0 +1,89 @@
+use crate::{
+    board::Board,
+    calendar::Day,
+    error::Result,
+    puzzle::Puzzle,
+    solve_all::{all_days, solve_day},
+    solver::{Solver, Strategy},
+    stats::SearchStats,
+};
+use std::time::{Duration, Instant};
+
+/// The time one strategy took over the benchmarked dates.
+#[derive(Debug, Default)]
+pub struct StrategyTiming {
+    /// The time taken over all the dates
+    pub total: Duration,
+    /// The longest time taken for one date
+    pub slowest: Duration,
+    /// The date that took longest, if any were timed
+    pub slowest_day: Option<String>,
+    /// Dates this strategy solved faster than the other one
+    pub faster_on: usize,
+    /// What the search did, over all the dates
+    pub stats: SearchStats,
+}
+
+impl StrategyTiming {
+    fn record(&mut self, day: &Day, elapsed: Duration) {
+        self.total += elapsed;
+        if elapsed > self.slowest {
+            self.slowest = elapsed;
+            self.slowest_day = Some(day.to_string());
+        }
+    }
+}
+
+/// The result of timing both backtracking strategies.
+#[derive(Debug, Default)]
+pub struct BenchmarkReport {
+    /// The number of dates timed
+    pub dates: usize,
+    /// How the piece-first strategy did
+    pub piece_first: StrategyTiming,
+    /// How the cell-first strategy did
+    pub cell_first: StrategyTiming,
+    /// Dates the strategies found different solutions for
+    pub disagreements: Vec<String>,
+}
+
+/// Times the piece-first and cell-first strategies on every `every`th date of a puzzle,
+/// one date at a time so the timings do not compete, checking that they find the same solutions.
+/// Calls `on_timed` with each date and its piece-first and cell-first times as it goes.
+pub fn benchmark<F>(puzzle: &Puzzle, every: usize, mut on_timed: F) -> Result<BenchmarkReport>
+where
+    F: FnMut(&Day, Duration, Duration),
+{
+    let mut report = BenchmarkReport::default();
+    for day in all_days(&puzzle.layout).iter().step_by(every.max(1)) {
+        let (piece_first, piece_first_time) =
+            timed(puzzle, day, Strategy::PieceFirst, &report.piece_first.stats)?;
+        let (cell_first, cell_first_time) =
+            timed(puzzle, day, Strategy::CellFirst, &report.cell_first.stats)?;
+
+        report.dates += 1;
+        report.piece_first.record(day, piece_first_time);
+        report.cell_first.record(day, cell_first_time);
+        match piece_first_time <= cell_first_time {
+            true => report.piece_first.faster_on += 1,
+            false => report.cell_first.faster_on += 1,
+        }
+        if piece_first != cell_first {
+            report.disagreements.push(day.to_string());
+        }
+        on_timed(day, piece_first_time, cell_first_time);
+    }
+    Ok(report)
+}
+
+/// Finds every solution for a day with a strategy, and how long it took.
+fn timed(
+    puzzle: &Puzzle,
+    day: &Day,
+    strategy: Strategy,
+    stats: &SearchStats,
+) -> Result<(Vec<Board>, Duration)> {
+    let start = Instant::now();
+    let solutions = solve_day(puzzle, day, Solver::Backtrack, strategy, stats)?;
+    Ok((solutions, start.elapsed()))
+}
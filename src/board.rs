@@ -1,48 +1,178 @@
 use crate::calendar::Day;
+use crate::dlx::Dlx;
+use crate::layout::Layout;
 use crate::piece::{Coordinates, Piece, Placement, Rotation};
 use crate::pieces::Pieces;
+use crate::solver::Strategy;
+use crate::stats::{Prune, SearchStats};
+use crate::stream::SolutionStream;
 use colored::Colorize;
 use itertools::Itertools;
 use lazy_static::lazy_static;
-use std::hash::{Hash, Hasher};
 
 use rayon::prelude::*;
 use std::collections::{HashMap, HashSet};
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::SyncSender;
+use std::sync::{Arc, Mutex};
 
 /// Configuration
-const BOARD_WIDTH: usize = 9;
-const BOARD_HEIGHT: usize = 6;
-const MISSING_CORNER_COORDINATES: (i32, i32) = (8, 5);
+/// The maximum number of cells a board can have, so that occupancy fits in a `u64`.
+pub const MAX_CELLS: usize = 64;
+
+/// For each board width, the cells in the first column of a board that wide, as far down as a mask goes.
+const FIRST_COLUMNS: [u64; MAX_CELLS + 1] = first_columns();
+
+const fn first_columns() -> [u64; MAX_CELLS + 1] {
+    let mut columns = [0; MAX_CELLS + 1];
+    let mut width = 1;
+    while width <= MAX_CELLS {
+        let mut cell = 0;
+        while cell < MAX_CELLS {
+            columns[width] |= 1 << cell;
+            cell += width;
+        }
+        width += 1;
+    }
+    columns
+}
 
 // Static pieces
 lazy_static! {
     static ref MONTH_PIECE: Piece = Pieces::get_month();
     static ref DAY_PIECE: Piece = Pieces::get_day();
     static ref WEEKDAY_PIECE: Piece = Pieces::get_weekday();
+    static ref YEAR_PIECE: Piece = Pieces::get_year();
     static ref corner_piece: Piece = Pieces::get_corner();
 }
 
-#[derive(Debug, Clone)]
+/// Finds the piece that covers cells with a symbol: one of `pieces`, or one the board places itself.
+fn piece_with_symbol(pieces: &[Piece], symbol: char) -> Option<&Piece> {
+    let fixed = [
+        &*MONTH_PIECE,
+        &*DAY_PIECE,
+        &*WEEKDAY_PIECE,
+        &*YEAR_PIECE,
+        &*corner_piece,
+    ];
+    pieces
+        .iter()
+        .chain(fixed)
+        .find(|piece| piece.symbol == symbol)
+}
+
+/// A board of at most `MAX_CELLS` cells, each blank or covered by a piece's symbol.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Board {
-    pub width: usize,                 // The width of the board
-    pub height: usize,                // The height of the board
-    pub grid: Vec<Vec<Option<char>>>, // Store Piece type for each cell
-    blank: char,                      // Symbol for empty cells
+    /// The width of the board
+    pub width: usize,
+    /// The height of the board
+    pub height: usize,
+    occupied: u64,          // Bit `y * width + x` is set for each occupied cell
+    cells: [u8; MAX_CELLS], // Piece symbol for each cell (0 for empty cells)
+    blank: char,            // Symbol for empty cells
 }
 
 impl Board {
     /// Creates a new Board with the given dimensions.
     pub fn new(width: usize, height: usize, blank: char) -> Self {
-        let grid = vec![vec![None; width]; height];
+        assert!(
+            width * height <= MAX_CELLS,
+            "A board can have at most {} cells.",
+            MAX_CELLS
+        );
         Board {
             width,
             height,
-            grid,
+            occupied: 0,
+            cells: [0; MAX_CELLS],
             blank,
         }
     }
 
+    /// Gets the bit for the cell at the given coordinates.
+    pub fn cell_bit(&self, x: usize, y: usize) -> u64 {
+        1 << (y * self.width + x)
+    }
+
+    /// Gets the mask of all cells on the board.
+    pub fn full_mask(&self) -> u64 {
+        match self.width * self.height {
+            MAX_CELLS => u64::MAX,
+            cells => (1 << cells) - 1,
+        }
+    }
+
+    /// Gets the mask of all cells that are still empty.
+    pub fn open_mask(&self) -> u64 {
+        self.full_mask() & !self.occupied
+    }
+
+    /// Gets the occupancy mask covering the given coordinates.
+    pub fn mask_of(&self, coordinates: &[Coordinates]) -> u64 {
+        coordinates.iter().fold(0, |mask, &(x, y)| {
+            mask | self.cell_bit(x as usize, y as usize)
+        })
+    }
+
+    /// Gets the coordinates of each cell in the mask, in row-major order.
+    pub fn coordinates_of(&self, mask: u64) -> Vec<Coordinates> {
+        (0..self.width * self.height)
+            .filter(|index| mask & (1 << index) != 0)
+            .map(|index| ((index % self.width) as i32, (index / self.width) as i32))
+            .collect()
+    }
+
+    /// Gets the symbol of the piece at the given coordinates, if any.
+    pub fn get(&self, x: usize, y: usize) -> Option<char> {
+        match self.cells[y * self.width + x] {
+            0 => None,
+            symbol => Some(symbol as char),
+        }
+    }
+
+    /// Gets the occupancy mask of every cell holding the given piece symbol.
+    pub fn mask_of_symbol(&self, symbol: char) -> u64 {
+        (0..self.width * self.height)
+            .filter(|&index| self.cells[index] as char == symbol)
+            .fold(0, |mask, index| mask | (1 << index))
+    }
+
+    /// Checks whether the cells in the mask form a single connected region.
+    pub fn is_connected(&self, mask: u64) -> bool {
+        if mask == 0 {
+            return true;
+        }
+
+        // Grow a region from the lowest cell, and see if it reaches every cell.
+        self.grow_within(mask & mask.wrapping_neg(), mask) == mask
+    }
+
+    /// Checks whether the cell at the given coordinates is occupied.
+    pub fn is_occupied(&self, x: usize, y: usize) -> bool {
+        self.occupied & self.cell_bit(x, y) != 0
+    }
+
+    /// Removes whatever occupies the cell at the given coordinates.
+    fn clear(&mut self, x: usize, y: usize) {
+        self.occupied &= !self.cell_bit(x, y);
+        self.cells[y * self.width + x] = 0;
+    }
+
+    /// Fills every cell in the mask with the given piece symbol.
+    pub fn fill_mask(&mut self, mask: u64, symbol: char) {
+        debug_assert!(symbol.is_ascii(), "Piece symbols must be ASCII.");
+        self.occupied |= mask;
+        let mut remaining = mask;
+        while remaining != 0 {
+            let index = remaining.trailing_zeros() as usize;
+            self.cells[index] = symbol as u8;
+            remaining &= remaining - 1;
+        }
+    }
+
+    /// Makes a hint from a solved board: a copy keeping only `hint` of its pieces,
+    /// chosen to touch each other and the board's edges as much as possible.
     pub fn hint_pieces(&self, hint: usize) -> Self {
         let coords_map = self.collect_piece_coordinates();
         let adjacency = Self::build_piece_adjacency(&coords_map);
@@ -55,9 +185,9 @@ impl Board {
 
     fn collect_piece_coordinates(&self) -> HashMap<char, Vec<(usize, usize)>> {
         let mut coords_map = HashMap::new();
-        for (y, row) in self.grid.iter().enumerate() {
-            for (x, &cell) in row.iter().enumerate() {
-                if let Some(ch) = cell {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if let Some(ch) = self.get(x, y) {
                     if ch.is_ascii_uppercase() {
                         coords_map.entry(ch).or_insert_with(Vec::new).push((x, y));
                     }
@@ -167,9 +297,9 @@ impl Board {
 
         for y in 0..hint_board.height {
             for x in 0..hint_board.width {
-                if let Some(ch) = hint_board.grid[y][x] {
+                if let Some(ch) = hint_board.get(x, y) {
                     if ch.is_ascii_uppercase() && !keep_set.contains(&ch) {
-                        hint_board.grid[y][x] = None; // remove the piece
+                        hint_board.clear(x, y); // remove the piece
                     }
                 }
             }
@@ -177,30 +307,35 @@ impl Board {
         hint_board
     }
 
-    pub fn make(day: &Day) -> Self {
+    /// Makes the board for a day, covering the day's labels and the cells that are not part of the layout.
+    pub fn make(layout: &Layout, day: &Day) -> Result<Self, PlacementError> {
         // Define the initial board.
-        let mut board = Board::new(BOARD_WIDTH, BOARD_HEIGHT, '·');
+        let mut board = Board::new(layout.width, layout.height, '·');
 
-        // Place the calendar pieces on the board.
-        board.place_piece(
-            &*MONTH_PIECE,
-            Placement::new(Rotation::Zero, false, day.month.to_coordinates()),
-        );
-        board.place_piece(
-            &*DAY_PIECE,
-            Placement::new(Rotation::Zero, false, day.day.to_coordinates()),
-        );
-        board.place_piece(
-            &*WEEKDAY_PIECE,
-            Placement::new(Rotation::Zero, false, day.weekday.to_coordinates()),
-        );
+        // Place the calendar pieces on the board, for each kind of label the layout has.
+        let calendar_pieces = [
+            (&*MONTH_PIECE, layout.month_cell(&day.month)),
+            (&*DAY_PIECE, layout.day_cell(&day.day)),
+            (
+                &*WEEKDAY_PIECE,
+                day.weekday.as_ref().and_then(|w| layout.weekday_cell(w)),
+            ),
+            (&*YEAR_PIECE, day.year.and_then(|y| layout.year_cell(y))),
+        ];
+        for (piece, cell) in calendar_pieces {
+            if let Some(coordinates) = cell {
+                board.place_piece(piece, Placement::new(Rotation::Zero, false, coordinates))?;
+            }
+        }
 
-        // Place the corner piece on the board.
-        board.place_piece(
-            &corner_piece,
-            Placement::new(Rotation::Zero, false, MISSING_CORNER_COORDINATES),
-        );
-        board
+        // Place the corner piece on every cell that is not part of the board.
+        for &coordinates in layout.blocked() {
+            board.place_piece(
+                &corner_piece,
+                Placement::new(Rotation::Zero, false, coordinates),
+            )?;
+        }
+        Ok(board)
     }
 
     /// Checks if a piece can be placed at the given base position and rotation, returning valid coordinates.
@@ -208,59 +343,107 @@ impl Board {
         &self,
         piece: &Piece,
         placement: Placement,
-    ) -> Result<Vec<Coordinates>, String> {
+    ) -> Result<Vec<Coordinates>, PlacementError> {
         let mut coordinates = Vec::new();
         for &(dx, dy) in piece.get_shape(placement.rotation, placement.flipped) {
             let x = placement.x + dx;
             let y = placement.y + dy;
             if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
-                return Err(format!("Position ({}, {}) is out of bounds.", x, y));
+                return Err(PlacementError::OutOfBounds { x, y });
             }
-            if self.grid[y as usize][x as usize].is_some() {
-                return Err(format!("Position ({}, {}) is already occupied.", x, y));
+            if self.is_occupied(x as usize, y as usize) {
+                return Err(PlacementError::Occupied { x, y });
             }
             coordinates.push((x, y));
         }
         Ok(coordinates)
     }
 
-    /// Places a piece on the board if it fits, using precomputed coordinates.
-    pub fn place_piece(&mut self, piece: &Piece, placement: Placement) -> bool {
-        match self.can_place_piece(piece, placement) {
-            Ok(coords) => {
-                for (x, y) in coords {
-                    self.grid[y as usize][x as usize] = Some(piece.symbol);
-                }
-                true
-            }
-            Err(err) => {
-                eprintln!("Failed to place piece: {}", err);
-                false
-            }
-        }
+    /// Places a piece on the board if it fits, or says why it does not.
+    pub fn place_piece(
+        &mut self,
+        piece: &Piece,
+        placement: Placement,
+    ) -> Result<(), PlacementError> {
+        let coordinates = self.can_place_piece(piece, placement)?;
+        self.fill_mask(self.mask_of(&coordinates), piece.symbol);
+        Ok(())
     }
 
     /// Output the board state in a simple string format, for hashing and unit testing
     /// Like get_display, but without colors or formatting or newlines
     pub fn serialize(&self) -> String {
-        self.grid
-            .iter()
-            .flat_map(|row| {
-                row.iter().map(|cell| match cell {
-                    Some(char) => char,
-                    None => &self.blank,
-                })
-            })
+        (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| self.get(x, y).unwrap_or(self.blank)))
             .collect()
     }
 
-    /// Displays the board (colored).
-    pub fn display(&self) {
-        for row in &self.grid {
-            for cell in row {
-                match cell {
+    /// Parses a board from the output of `serialize`, the inverse of that method.
+    /// Whitespace is ignored, so a multi-line grid with one row per line is accepted too.
+    /// Apart from the date and corner cells, every symbol must be one of `pieces`.
+    pub fn deserialize(
+        width: usize,
+        height: usize,
+        text: &str,
+        pieces: &[Piece],
+    ) -> Result<Board, ParseError> {
+        let size = width.saturating_mul(height);
+        if size > MAX_CELLS {
+            return Err(ParseError::TooManyCells(size));
+        }
+
+        let cells: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+        if cells.len() != width * height {
+            return Err(ParseError::WrongLength {
+                expected: width * height,
+                found: cells.len(),
+            });
+        }
+
+        let mut board = Board::new(width, height, '·');
+        let mut masks: Vec<(char, u64)> = Vec::new();
+        for (index, &symbol) in cells.iter().enumerate() {
+            if symbol == board.blank {
+                continue;
+            }
+            if !symbol.is_ascii() || piece_with_symbol(pieces, symbol).is_none() {
+                return Err(ParseError::UnknownSymbol {
+                    symbol,
+                    x: index % width,
+                    y: index / width,
+                });
+            }
+            match masks.iter_mut().find(|(s, _)| *s == symbol) {
+                Some((_, mask)) => *mask |= 1 << index,
+                None => masks.push((symbol, 1 << index)),
+            }
+        }
+
+        // Cells outside the board each get their own corner piece, so only the
+        // uppercase puzzle pieces have to be connected.
+        for (symbol, mask) in masks {
+            if symbol.is_ascii_uppercase() && !board.is_connected(mask) {
+                return Err(ParseError::DisconnectedPiece {
+                    symbol,
+                    cells: board.coordinates_of(mask),
+                });
+            }
+            board.fill_mask(mask, symbol);
+        }
+
+        Ok(board)
+    }
+
+    /// Displays the board, coloring each cell like the piece in `pieces` that covers it.
+    pub fn display(&self, pieces: &[Piece]) {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                match self.get(x, y) {
                     Some(char) => {
-                        let piece = Pieces::by_symbol(*char);
+                        let Some(piece) = piece_with_symbol(pieces, char) else {
+                            print!(" {} ", char);
+                            continue;
+                        };
                         print!(
                             "{}{}{}",
                             " ".on_color(piece.bg),
@@ -275,79 +458,106 @@ impl Board {
                     None => print!(" {} ", self.blank),
                 }
             }
-            print!("{}", '\n'); // Newline after each row
+            println!(); // Newline after each row
         }
     }
 
-    /// Scans the board for all contiguous blank areas.
-    /// Returns a vector of sizes of each blank region.
-    pub fn scan_blank_areas(&self) -> Vec<usize> {
-        let mut visited = vec![vec![false; self.width]; self.height];
-        let mut blank_areas = Vec::new();
+    /// Finds all contiguous blank areas, growing each one from its lowest cell a step at a time
+    /// with bitwise operations on the occupancy mask, instead of visiting cells one by one.
+    /// Returns the occupancy mask of each blank region, in order of their lowest cells.
+    pub fn blank_regions(&self) -> Vec<u64> {
+        let mut regions = Vec::new();
+        let mut open = self.open_mask();
+        while open != 0 {
+            let region = self.grow_within(open & open.wrapping_neg(), open);
+            regions.push(region);
+            open &= !region;
+        }
+        regions
+    }
 
-        for y in 0..self.height {
-            for x in 0..self.width {
-                // Skip visited cells and non-blank cells
-                if visited[y][x] || self.grid[y][x].is_some() {
-                    continue;
-                }
+    /// Grows a region from `seed` into neighboring cells of `within`, until it stops growing.
+    fn grow_within(&self, seed: u64, within: u64) -> u64 {
+        // Cells in the first and last columns, which must not spread around to the next row
+        let first_column = FIRST_COLUMNS[self.width];
+        let last_column = first_column << (self.width - 1);
+        let not_left = within & !first_column; // Cells that can be reached from the left
+        let not_right = within & !last_column; // Cells that can be reached from the right
 
-                // Perform flood fill to calculate the size of this blank area
-                let size = self.flood_fill_blank(x, y, &mut visited);
-                blank_areas.push(size);
+        // On a board one row high, the rows above and below are shifted out entirely.
+        let width = self.width as u32;
+        let mut region = seed & within;
+        loop {
+            let grown = region
+                | ((region << 1) & not_left)
+                | ((region >> 1) & not_right)
+                | (region.checked_shl(width).unwrap_or(0) & within)
+                | (region.checked_shr(width).unwrap_or(0) & within);
+            if grown == region {
+                return region;
             }
+            region = grown;
         }
-
-        blank_areas
     }
 
-    /// Performs a flood fill to calculate the size of a blank area.
-    fn flood_fill_blank(
-        &self,
-        start_x: usize,
-        start_y: usize,
-        visited: &mut Vec<Vec<bool>>,
-    ) -> usize {
-        let mut stack = vec![(start_x, start_y)];
-        let mut size = 0;
-
-        while let Some((x, y)) = stack.pop() {
-            // Skip out-of-bounds or already visited cells
-            if x >= self.width || y >= self.height || visited[y][x] || self.grid[y][x].is_some() {
-                continue;
+    /// Finds a rule showing that some blank region cannot be filled by the remaining pieces:
+    /// no combination of them has the region's size, or the region is small enough to need
+    /// exactly one or two pieces and no such pieces fit it. Returns `None` if none applies.
+    pub fn unfillable_region(&self, remaining: &RemainingPieces) -> Option<Prune> {
+        let pieces = remaining.pieces;
+        for region in self.blank_regions() {
+            let size = region.count_ones() as usize;
+            if remaining.sums & (1 << size) == 0 {
+                return Some(Prune::Size);
             }
 
-            // Mark the cell as visited and increase the size
-            visited[y][x] = true;
-            size += 1;
-
-            // Add neighboring cells to the stack
-            stack.push((x + 1, y)); // Right
-            stack.push((x.wrapping_sub(1), y)); // Left (with wrapping prevention)
-            stack.push((x, y + 1)); // Down
-            stack.push((x, y.wrapping_sub(1))); // Up (with wrapping prevention)
+            let one_fits = || pieces.iter().any(|piece| piece.covers_exactly(region));
+            if size < remaining.two_pieces && !one_fits() {
+                return Some(Prune::Shape);
+            }
+            if size < remaining.three_pieces && !one_fits() && !Self::two_fit(region, pieces) {
+                return Some(Prune::Pair);
+            }
         }
-
-        size
+        None
     }
 
-    /// Checks if the board contains any dead-end blank areas.
-    pub fn has_dead_end_blanks_not_divisible_by(&self, divisible_by: usize) -> bool {
-        self.scan_blank_areas()
-            .iter()
-            .any(|&size| size % divisible_by != 0)
+    /// Checks whether two of the pieces can fill a region exactly between them.
+    fn two_fit(region: u64, pieces: &[&Piece]) -> bool {
+        // One of the two pieces must cover the region's lowest cell.
+        let lowest = region & region.wrapping_neg();
+        pieces.iter().enumerate().any(|(i, piece)| {
+            piece.get_allowed_masks().iter().any(|&mask| {
+                mask & lowest != 0
+                    && mask & !region == 0
+                    && pieces
+                        .iter()
+                        .enumerate()
+                        .any(|(j, other)| j != i && other.covers_exactly(region ^ mask))
+            })
+        })
     }
 
-    /// Finds all valid boards by placing a new piece in all possible positions and rotations.
-    pub fn find_all_valid_boards_with_new_piece(&self, piece: &Piece) -> Vec<Board> {
+    /// Finds all valid boards by placing a new piece in all possible positions and rotations,
+    /// leaving only blank areas that the remaining pieces could fill.
+    /// Counts the placements tried, and those pruned by each rule, in `stats`.
+    pub fn find_all_valid_boards_with_new_piece(
+        &self,
+        piece: &Piece,
+        remaining: &[&Piece],
+        stats: &SearchStats,
+    ) -> Vec<Board> {
         let mut valid_boards: Vec<Board> = Vec::new();
+        let remaining = RemainingPieces::new(remaining);
 
-        for &placement in piece.get_allowed_placements() {
-            if self.can_place_piece(piece, placement).is_ok() {
+        for &mask in piece.get_allowed_masks() {
+            if self.occupied & mask == 0 {
+                stats.record_placement();
                 let mut new_board = self.clone();
-                new_board.place_piece(piece, placement);
-                if !new_board.has_dead_end_blanks_not_divisible_by(5) {
-                    valid_boards.push(new_board);
+                new_board.fill_mask(mask, piece.symbol);
+                match new_board.unfillable_region(&remaining) {
+                    Some(rule) => stats.record_prune(rule),
+                    None => valid_boards.push(new_board),
                 }
             }
         }
@@ -356,46 +566,901 @@ impl Board {
     }
 
     /// Recursively attempts to place all pieces on the board.
-    /// Returns a vector of boards that successfully place all pieces.
-    pub fn find_boards_placing_all_pieces(
+    /// Calls `on_solution` with each board that successfully places all pieces as soon as it is found.
+    /// The search winds down once `stop` is set, which happens after the first solution
+    /// unless `find_all` is used, or when `on_solution` returns `false` to ask for no more.
+    pub fn find_boards_placing_all_pieces<F>(
         &self,
         pieces: &[&Piece],
-        found: &AtomicBool,
+        on_solution: &F,
+        stop: &AtomicBool,
+        stats: &SearchStats,
         find_all: bool,
-    ) -> HashSet<Board> {
+    ) where
+        F: Fn(Board) -> bool + Sync,
+    {
+        self.find_placing_recursive(pieces, 0, on_solution, stop, stats, find_all);
+    }
+
+    /// Recursive step of `find_boards_placing_all_pieces`, with `depth` pieces already placed.
+    fn find_placing_recursive<F>(
+        &self,
+        pieces: &[&Piece],
+        depth: usize,
+        on_solution: &F,
+        stop: &AtomicBool,
+        stats: &SearchStats,
+        find_all: bool,
+    ) where
+        F: Fn(Board) -> bool + Sync,
+    {
+        if stop.load(Ordering::Relaxed) {
+            return;
+        }
+        stats.record_node(depth);
+
         if pieces.is_empty() {
-            if !find_all {
-                found.store(true, Ordering::Relaxed);
+            stats.record_solution();
+            if !on_solution(self.clone()) || !find_all {
+                stop.store(true, Ordering::Relaxed);
+            }
+            return;
+        }
+
+        let piece = pieces[0];
+        let valid_boards = self.find_all_valid_boards_with_new_piece(piece, &pieces[1..], stats);
+        stats.record_branches(depth, valid_boards.len());
+
+        valid_boards.into_par_iter().for_each(|valid_board| {
+            valid_board.find_placing_recursive(
+                &pieces[1..],
+                depth + 1,
+                on_solution,
+                stop,
+                stats,
+                find_all,
+            );
+            stats.record_branch_done(depth);
+        });
+    }
+
+    /// Like `find_boards_placing_all_pieces`, but instead of placing the pieces in order, always
+    /// fills the open cell that the fewest remaining placements can cover next, branching on
+    /// which piece covers it. A cell that nothing can cover any more is a dead end.
+    pub fn find_boards_placing_all_pieces_cell_first<F>(
+        &self,
+        pieces: &[&Piece],
+        on_solution: &F,
+        stop: &AtomicBool,
+        stats: &SearchStats,
+        find_all: bool,
+    ) where
+        F: Fn(Board) -> bool + Sync,
+    {
+        // The placements that cover each cell.
+        let mut covers: Vec<Vec<Cover>> = vec![Vec::new(); self.width * self.height];
+        for (index, piece) in pieces.iter().enumerate() {
+            for &mask in piece.get_allowed_masks() {
+                if self.occupied & mask == 0 {
+                    let cover = Cover {
+                        piece: index,
+                        symbol: piece.symbol,
+                        mask,
+                    };
+                    let mut remaining = mask;
+                    while remaining != 0 {
+                        covers[remaining.trailing_zeros() as usize].push(cover);
+                        remaining &= remaining - 1;
+                    }
+                }
             }
-            return HashSet::from([self.clone()]);
         }
 
-        if !find_all && found.load(Ordering::Relaxed) {
-            return HashSet::new();
+        let covers = CoverTable {
+            covers,
+            pieces: pieces.len(),
+        };
+        let unplaced = (0..pieces.len()).fold(0u64, |unplaced, i| unplaced | 1 << i);
+        self.find_cell_first_recursive(&covers, unplaced, on_solution, stop, stats, find_all);
+    }
+
+    /// Recursive step of `find_boards_placing_all_pieces_cell_first`.
+    /// Bit `i` of `unplaced` is set while the `i`th piece has not been placed.
+    fn find_cell_first_recursive<F>(
+        &self,
+        covers: &CoverTable,
+        unplaced: u64,
+        on_solution: &F,
+        stop: &AtomicBool,
+        stats: &SearchStats,
+        find_all: bool,
+    ) where
+        F: Fn(Board) -> bool + Sync,
+    {
+        if stop.load(Ordering::Relaxed) {
+            return;
+        }
+        let depth = covers.pieces - unplaced.count_ones() as usize;
+        stats.record_node(depth);
+
+        if unplaced == 0 {
+            if self.open_mask() == 0 {
+                stats.record_solution();
+                if !on_solution(self.clone()) || !find_all {
+                    stop.store(true, Ordering::Relaxed);
+                }
+            }
+            return;
+        }
+
+        let fits =
+            |cover: &&Cover| unplaced & (1 << cover.piece) != 0 && self.occupied & cover.mask == 0;
+        let Some(cell) = self.most_constrained_cell(&covers.covers, &fits) else {
+            return;
+        };
+
+        let branches: Vec<&Cover> = covers.covers[cell].iter().filter(fits).collect();
+        stats.record_branches(depth, branches.len());
+        branches.into_par_iter().for_each(|cover| {
+            stats.record_placement();
+            let mut board = self.clone();
+            board.fill_mask(cover.mask, cover.symbol);
+            board.find_cell_first_recursive(
+                covers,
+                unplaced & !(1 << cover.piece),
+                on_solution,
+                stop,
+                stats,
+                find_all,
+            );
+            stats.record_branch_done(depth);
+        });
+    }
+
+    /// Finds the open cell with the fewest placements that `fits` and cover it, the first one
+    /// on a tie. Returns `None` if there are no open cells, or one of them cannot be covered.
+    fn most_constrained_cell<P>(&self, covers: &[Vec<Cover>], fits: &P) -> Option<usize>
+    where
+        P: Fn(&&Cover) -> bool,
+    {
+        let mut best: Option<(usize, usize)> = None; // (cell, placements)
+        let mut open = self.open_mask();
+        while open != 0 {
+            let cell = open.trailing_zeros() as usize;
+            open &= open - 1;
+
+            let placements = covers[cell].iter().filter(fits).count();
+            if placements == 0 {
+                return None;
+            }
+            if best.is_none_or(|(_, fewest)| placements < fewest) {
+                best = Some((cell, placements));
+                if placements == 1 {
+                    break;
+                }
+            }
+        }
+        best.map(|(cell, _)| cell)
+    }
+
+    /// Solves the board as an exact cover problem using Dancing Links.
+    /// Each piece must be used exactly once, and each open cell covered exactly once.
+    /// Sends the same boards as `find_boards_placing_all_pieces`, with the same stopping rules.
+    pub fn find_boards_placing_all_pieces_dlx<F>(
+        &self,
+        pieces: &[&Piece],
+        on_solution: &F,
+        stop: &AtomicBool,
+        find_all: bool,
+    ) where
+        F: Fn(Board) -> bool,
+    {
+        let (mut dlx, rows) = self.build_exact_cover(pieces);
+
+        dlx.solve(stop, |solution| {
+            let mut board = self.clone();
+            for &row in solution {
+                let (piece_index, mask) = rows[row];
+                board.fill_mask(mask, pieces[piece_index].symbol);
+            }
+
+            if !on_solution(board) || !find_all {
+                stop.store(true, Ordering::Relaxed);
+            }
+            !stop.load(Ordering::Relaxed)
+        });
+    }
+
+    /// Finds the board placing all pieces whose `serialize()` output is lexicographically smallest.
+    /// Branches are searched in parallel, and any branch whose settled cells already sort
+    /// after the best solution so far is abandoned, so the result never depends on thread timing.
+    pub fn find_smallest_board_placing_all_pieces(
+        &self,
+        pieces: &[&Piece],
+        stop: &AtomicBool,
+        stats: &SearchStats,
+    ) -> Option<Board> {
+        let best: Mutex<Option<(String, Board)>> = Mutex::new(None);
+        self.find_smallest_recursive(pieces, 0, &best, stop, stats);
+        best.into_inner().unwrap().map(|(_, board)| board)
+    }
+
+    /// Recursive step of `find_smallest_board_placing_all_pieces`, with `depth` pieces placed.
+    fn find_smallest_recursive(
+        &self,
+        pieces: &[&Piece],
+        depth: usize,
+        best: &Mutex<Option<(String, Board)>>,
+        stop: &AtomicBool,
+        stats: &SearchStats,
+    ) {
+        if stop.load(Ordering::Relaxed) {
+            return;
+        }
+        stats.record_node(depth);
+
+        // Every cell before the first blank one is settled, so prune if those already lose.
+        let prefix = self.settled_prefix();
+        if let Some((best_serialized, _)) = best.lock().unwrap().as_ref() {
+            if prefix.as_str() > &best_serialized[..prefix.len()] {
+                return;
+            }
+        }
+
+        if pieces.is_empty() {
+            stats.record_solution();
+            let mut best = best.lock().unwrap();
+            if best.as_ref().is_none_or(|(b, _)| prefix < *b) {
+                *best = Some((prefix, self.clone()));
+            }
+            return;
         }
 
         let piece = pieces[0];
-        let valid_boards = self.find_all_valid_boards_with_new_piece(piece);
+        let valid_boards = self.find_all_valid_boards_with_new_piece(piece, &pieces[1..], stats);
+        stats.record_branches(depth, valid_boards.len());
 
-        valid_boards
-            .into_par_iter()
-            .flat_map(|valid_board| {
-                valid_board.find_boards_placing_all_pieces(&pieces[1..], found, find_all)
+        valid_boards.into_par_iter().for_each(|valid_board| {
+            valid_board.find_smallest_recursive(&pieces[1..], depth + 1, best, stop, stats);
+            stats.record_branch_done(depth);
+        });
+    }
+
+    /// Serializes the cells before the first blank one, which no later placement can change.
+    fn settled_prefix(&self) -> String {
+        let first_blank = self.open_mask().trailing_zeros() as usize;
+        let settled = first_blank.min(self.width * self.height);
+        self.cells[..settled].iter().map(|&c| c as char).collect()
+    }
+
+    /// Backtracks with the given search order, as `find_boards_placing_all_pieces`
+    /// or `find_boards_placing_all_pieces_cell_first`.
+    pub fn find_boards_placing_all_pieces_with<F>(
+        &self,
+        strategy: Strategy,
+        pieces: &[&Piece],
+        on_solution: &F,
+        stop: &AtomicBool,
+        stats: &SearchStats,
+        find_all: bool,
+    ) where
+        F: Fn(Board) -> bool + Sync,
+    {
+        match strategy {
+            Strategy::PieceFirst => {
+                self.find_boards_placing_all_pieces(pieces, on_solution, stop, stats, find_all)
+            }
+            Strategy::CellFirst => self.find_boards_placing_all_pieces_cell_first(
+                pieces,
+                on_solution,
+                stop,
+                stats,
+                find_all,
+            ),
+        }
+    }
+
+    /// Streams the boards that place all pieces, searching on a background thread.
+    /// With `deterministic`, boards come in `serialize()` order, and the first one is
+    /// always the lexicographically smallest solution, whatever the `strategy`.
+    /// The search counts what it does in `stats`, which can be watched while it runs.
+    pub fn stream_boards_placing_all_pieces(
+        &self,
+        pieces: &[&Piece],
+        find_all: bool,
+        deterministic: bool,
+        strategy: Strategy,
+        stats: Arc<SearchStats>,
+    ) -> SolutionStream {
+        let board = self.clone();
+        let pieces: Vec<Piece> = pieces.iter().map(|&p| p.clone()).collect();
+        let search = move |solutions: &SyncSender<Board>, stop: &AtomicBool| {
+            let pieces: Vec<&Piece> = pieces.iter().collect();
+            match deterministic && !find_all {
+                true => {
+                    if let Some(smallest) =
+                        board.find_smallest_board_placing_all_pieces(&pieces, stop, &stats)
+                    {
+                        let _ = solutions.send(smallest);
+                    }
+                }
+                false => board.find_boards_placing_all_pieces_with(
+                    strategy,
+                    &pieces,
+                    &|b| solutions.send(b).is_ok(),
+                    stop,
+                    &stats,
+                    find_all,
+                ),
+            }
+        };
+
+        match deterministic && find_all {
+            true => SolutionStream::spawn_sorted(search, find_all),
+            false => SolutionStream::spawn(search),
+        }
+    }
+
+    /// Streams the boards that place all pieces, using Dancing Links on a background thread.
+    /// With `deterministic`, every solution is found first and then streamed in `serialize()` order.
+    pub fn stream_boards_placing_all_pieces_dlx(
+        &self,
+        pieces: &[&Piece],
+        find_all: bool,
+        deterministic: bool,
+    ) -> SolutionStream {
+        let board = self.clone();
+        let pieces: Vec<Piece> = pieces.iter().map(|&p| p.clone()).collect();
+        let search = move |solutions: &SyncSender<Board>, stop: &AtomicBool| {
+            let pieces: Vec<&Piece> = pieces.iter().collect();
+            board.find_boards_placing_all_pieces_dlx(
+                &pieces,
+                &|b| solutions.send(b).is_ok(),
+                stop,
+                find_all || deterministic,
+            );
+        };
+
+        match deterministic {
+            true => SolutionStream::spawn_sorted(search, find_all),
+            false => SolutionStream::spawn(search),
+        }
+    }
+
+    /// Counts the solutions using Dancing Links, without building any boards.
+    pub fn count_boards_placing_all_pieces_dlx(
+        &self,
+        pieces: &[&Piece],
+        breakdown: bool,
+    ) -> SolutionCount {
+        let (mut dlx, rows) = self.build_exact_cover(pieces);
+
+        let mut count = SolutionCount::default();
+        let mut by_first_placement: HashMap<u64, usize> = HashMap::new();
+        dlx.solve(&AtomicBool::new(false), |solution| {
+            count.total += 1;
+            if breakdown {
+                if let Some(&(_, mask)) = solution.iter().map(|&row| &rows[row]).find(|r| r.0 == 0)
+                {
+                    *by_first_placement.entry(mask).or_insert(0) += 1;
+                }
+            }
+            true
+        });
+
+        count.by_first_placement = by_first_placement.into_iter().collect();
+        count.by_first_placement.sort();
+        count
+    }
+
+    /// Builds the exact cover matrix for placing all pieces on the open cells.
+    /// Returns the matrix and, for each row, the piece index and occupancy mask it places.
+    fn build_exact_cover(&self, pieces: &[&Piece]) -> (Dlx, Vec<(usize, u64)>) {
+        // Columns: one per piece, followed by one per open cell.
+        let mut cell_columns = [0; MAX_CELLS];
+        let mut column_count = pieces.len();
+        let open = self.open_mask();
+        for (index, column) in cell_columns.iter_mut().enumerate() {
+            if open & (1 << index) != 0 {
+                *column = column_count;
+                column_count += 1;
+            }
+        }
+
+        // Rows: every distinct placement of every piece that fits on the open cells.
+        let mut rows: Vec<(usize, u64)> = Vec::new();
+        let mut dlx = Dlx::new(column_count);
+        for (piece_index, piece) in pieces.iter().enumerate() {
+            for &mask in piece.get_allowed_masks() {
+                if self.occupied & mask == 0 {
+                    let mut columns = vec![piece_index];
+                    let mut remaining = mask;
+                    while remaining != 0 {
+                        columns.push(cell_columns[remaining.trailing_zeros() as usize]);
+                        remaining &= remaining - 1;
+                    }
+                    dlx.add_row(rows.len(), &columns);
+                    rows.push((piece_index, mask));
+                }
+            }
+        }
+
+        (dlx, rows)
+    }
+
+    /// Counts the boards that successfully place all pieces, without building them.
+    /// Only the count is kept, so memory use does not grow with the number of solutions.
+    /// With `breakdown`, also counts the solutions for each placement of the first piece.
+    pub fn count_boards_placing_all_pieces(
+        &self,
+        pieces: &[&Piece],
+        breakdown: bool,
+    ) -> SolutionCount {
+        let masks: Vec<Vec<u64>> = pieces
+            .iter()
+            .map(|p| p.get_allowed_masks().to_vec())
+            .collect();
+
+        let Some(first_masks) = masks.first() else {
+            return SolutionCount {
+                total: 1,
+                by_first_placement: Vec::new(),
+            };
+        };
+
+        let remaining = RemainingPieces::new(&pieces[1..]);
+        let by_first_placement: Vec<(u64, usize)> = first_masks
+            .par_iter()
+            .filter_map(|&mask| {
+                let board = self.with_mask_if_viable(mask, pieces[0].symbol, &remaining)?;
+                let count = board.count_with_masks(&pieces[1..], &masks[1..]);
+                Some((mask, count))
             })
-            .collect()
+            .filter(|&(_, count)| count > 0)
+            .collect();
+
+        SolutionCount {
+            total: by_first_placement.iter().map(|&(_, count)| count).sum(),
+            by_first_placement: match breakdown {
+                true => by_first_placement,
+                false => Vec::new(),
+            },
+        }
+    }
+
+    /// Counts the solutions by filling the most constrained cell first.
+    /// Each solution is built, but only counted, so memory use does not grow either.
+    pub fn count_boards_placing_all_pieces_cell_first(
+        &self,
+        pieces: &[&Piece],
+        breakdown: bool,
+    ) -> SolutionCount {
+        let total = AtomicUsize::new(0);
+        let by_first_placement: Mutex<HashMap<u64, usize>> = Mutex::new(HashMap::new());
+        let on_solution = |board: Board| {
+            total.fetch_add(1, Ordering::Relaxed);
+            if let (true, Some(first)) = (breakdown, pieces.first()) {
+                let mask = board.mask_of_symbol(first.symbol);
+                *by_first_placement.lock().unwrap().entry(mask).or_insert(0) += 1;
+            }
+            true
+        };
+        self.find_boards_placing_all_pieces_cell_first(
+            pieces,
+            &on_solution,
+            &AtomicBool::new(false),
+            &SearchStats::default(),
+            true,
+        );
+
+        let mut by_first_placement: Vec<(u64, usize)> = by_first_placement
+            .into_inner()
+            .unwrap()
+            .into_iter()
+            .collect();
+        by_first_placement.sort();
+        SolutionCount {
+            total: total.into_inner(),
+            by_first_placement,
+        }
+    }
+
+    /// Recursive step of `count_boards_placing_all_pieces`.
+    fn count_with_masks(&self, pieces: &[&Piece], masks: &[Vec<u64>]) -> usize {
+        if pieces.is_empty() {
+            return 1;
+        }
+
+        let remaining = RemainingPieces::new(&pieces[1..]);
+        masks[0]
+            .par_iter()
+            .filter_map(|&mask| self.with_mask_if_viable(mask, pieces[0].symbol, &remaining))
+            .map(|board| board.count_with_masks(&pieces[1..], &masks[1..]))
+            .sum()
+    }
+
+    /// Places a piece by its mask, returning the new board if it fits
+    /// and leaves only blank areas that the `remaining` pieces could fill.
+    fn with_mask_if_viable(
+        &self,
+        mask: u64,
+        symbol: char,
+        remaining: &RemainingPieces,
+    ) -> Option<Board> {
+        if self.occupied & mask != 0 {
+            return None;
+        }
+        let mut board = self.clone();
+        board.fill_mask(mask, symbol);
+        match board.unfillable_region(remaining) {
+            Some(_) => None,
+            None => Some(board),
+        }
     }
 }
 
-impl PartialEq for Board {
-    fn eq(&self, other: &Self) -> bool {
-        self.serialize() == other.serialize()
+/// The pieces still to be placed, with what `unfillable_region` needs to know about their sizes.
+/// These only change with the pieces, so they are worked out once for every placement tried.
+pub struct RemainingPieces<'a> {
+    pieces: &'a [&'a Piece],
+    sums: u128,          // Bit n is set if some of the pieces have n cells between them
+    two_pieces: usize,   // Cells in the two smallest pieces, or `usize::MAX` if there are fewer
+    three_pieces: usize, // Cells in the three smallest pieces, or `usize::MAX` if there are fewer
+}
+
+impl<'a> RemainingPieces<'a> {
+    /// Works out what pruning needs to know about the sizes of `pieces`.
+    pub fn new(pieces: &'a [&'a Piece]) -> Self {
+        let sums = pieces
+            .iter()
+            .fold(1u128, |sums, piece| sums | sums << piece.size());
+
+        // Regions smaller than the two (or three) smallest pieces need fewer pieces than that.
+        let mut sizes: Vec<usize> = pieces.iter().map(|piece| piece.size()).collect();
+        sizes.sort_unstable();
+        let smallest = |n: usize| match sizes.len() >= n {
+            true => sizes[..n].iter().sum(),
+            false => usize::MAX,
+        };
+
+        RemainingPieces {
+            pieces,
+            sums,
+            two_pieces: smallest(2),
+            three_pieces: smallest(3),
+        }
     }
 }
 
-impl Eq for Board {}
+/// The placements covering each cell, for the cell-first search.
+struct CoverTable {
+    covers: Vec<Vec<Cover>>, // Indexed by cell
+    pieces: usize,           // The number of pieces being placed
+}
+
+/// A placement of a piece, as the cell-first search looks it up by the cells it covers.
+#[derive(Clone, Copy)]
+struct Cover {
+    piece: usize, // Index of the piece in the pieces being placed
+    symbol: char,
+    mask: u64,
+}
+
+/// An error from placing a piece on a board.
+#[derive(Debug, PartialEq)]
+pub enum PlacementError {
+    /// A cell of the piece that is off the board
+    OutOfBounds {
+        /// The column of the cell
+        x: i32,
+        /// The row of the cell
+        y: i32,
+    },
+    /// A cell of the piece that is already covered
+    Occupied {
+        /// The column of the cell
+        x: i32,
+        /// The row of the cell
+        y: i32,
+    },
+}
+
+impl std::error::Error for PlacementError {}
+
+impl std::fmt::Display for PlacementError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlacementError::OutOfBounds { x, y } => {
+                write!(f, "Position ({}, {}) is out of bounds", x, y)
+            }
+            PlacementError::Occupied { x, y } => {
+                write!(f, "Position ({}, {}) is already occupied", x, y)
+            }
+        }
+    }
+}
+
+/// An error from parsing a serialized board.
+#[derive(Debug, PartialEq)]
+pub enum ParseError {
+    /// A width and height with more cells than a board can have
+    TooManyCells(usize),
+    /// Text with a different number of cells than the board
+    WrongLength {
+        /// The cells on the board
+        expected: usize,
+        /// The cells in the text
+        found: usize,
+    },
+    /// A symbol that is not one of the pieces
+    UnknownSymbol {
+        /// The symbol
+        symbol: char,
+        /// The column of its cell
+        x: usize,
+        /// The row of its cell
+        y: usize,
+    },
+    /// A piece whose cells are not all connected
+    DisconnectedPiece {
+        /// The piece's symbol
+        symbol: char,
+        /// The piece's cells
+        cells: Vec<Coordinates>,
+    },
+}
+
+impl std::error::Error for ParseError {}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::TooManyCells(cells) => {
+                write!(
+                    f,
+                    "A board can have at most {} cells, not {}",
+                    MAX_CELLS, cells
+                )
+            }
+            ParseError::WrongLength { expected, found } => {
+                write!(f, "Expected {} cells, found {}", expected, found)
+            }
+            ParseError::UnknownSymbol { symbol, x, y } => {
+                write!(f, "Unknown symbol '{}' at ({}, {})", symbol, x, y)
+            }
+            ParseError::DisconnectedPiece { symbol, cells } => {
+                write!(f, "Piece {} is not connected:", symbol)?;
+                for (x, y) in cells {
+                    write!(f, " ({}, {})", x, y)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The number of solutions for a board.
+#[derive(Debug, Clone, Default)]
+pub struct SolutionCount {
+    /// The number of solutions
+    pub total: usize,
+    /// Solution counts for each placement (occupancy mask) of the first piece, if requested.
+    pub by_first_placement: Vec<(u64, usize)>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::march_3_tuesday;
+
+    /// Gets every solution a stream gives, in `serialize()` order.
+    fn sorted(stream: SolutionStream) -> Vec<String> {
+        let mut solutions: Vec<String> = stream.map(|b| b.serialize()).collect();
+        solutions.sort();
+        solutions
+    }
+
+    #[test]
+    fn test_dlx_matches_known_solutions() {
+        let known = march_3_tuesday();
+        let pieces: Vec<&Piece> = known.pieces.iter().collect();
+
+        let solutions = sorted(
+            known
+                .board
+                .stream_boards_placing_all_pieces_dlx(&pieces, true, false),
+        );
+        assert_eq!(solutions, known.solutions);
+    }
+
+    #[test]
+    fn test_cell_first_matches_piece_first() {
+        let known = march_3_tuesday();
+        let (board, expected) = (&known.board, &known.solutions);
+        let pieces: Vec<&Piece> = known.pieces.iter().collect();
+
+        let stats = Arc::new(SearchStats::default());
+        let stream = |strategy, stats| {
+            board.stream_boards_placing_all_pieces(&pieces, true, false, strategy, stats)
+        };
+        let piece_first = sorted(stream(Strategy::PieceFirst, Arc::default()));
+        let cell_first = sorted(stream(Strategy::CellFirst, stats.clone()));
+
+        assert_eq!(&piece_first, expected);
+        assert_eq!(cell_first, piece_first);
+        assert_eq!(
+            board.count_boards_placing_all_pieces(&pieces, false).total,
+            expected.len()
+        );
+        assert_eq!(
+            board
+                .count_boards_placing_all_pieces_cell_first(&pieces, false)
+                .total,
+            expected.len()
+        );
+
+        // Every top-level branch was searched, and every solution counted.
+        assert_eq!(stats.solutions.load(Ordering::Relaxed), expected.len());
+        assert_eq!(stats.nodes_by_depth[0].load(Ordering::Relaxed), 1);
+        assert_eq!(
+            stats.branches_done.load(Ordering::Relaxed),
+            stats.branches.load(Ordering::Relaxed)
+        );
+    }
 
-impl Hash for Board {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.serialize().hash(state);
+    #[test]
+    fn test_deterministic_gives_smallest_solution() {
+        let known = march_3_tuesday();
+        let board = &known.board;
+        let pieces: Vec<&Piece> = known.pieces.iter().collect();
+        let smallest = &known.solutions[0..1];
+
+        let found = board
+            .find_smallest_board_placing_all_pieces(
+                &pieces,
+                &AtomicBool::new(false),
+                &Arc::default(),
+            )
+            .map(|b| b.serialize());
+        assert_eq!(found.as_ref(), smallest.first());
+
+        for strategy in [Strategy::PieceFirst, Strategy::CellFirst] {
+            let stream = board.stream_boards_placing_all_pieces(
+                &pieces,
+                false,
+                true,
+                strategy,
+                Arc::default(),
+            );
+            assert_eq!(sorted(stream), smallest);
+        }
+        let stream = board.stream_boards_placing_all_pieces_dlx(&pieces, false, true);
+        assert_eq!(sorted(stream), smallest);
+
+        // With --all, every solution comes in order.
+        let stream = board.stream_boards_placing_all_pieces_dlx(&pieces, true, true);
+        let in_order: Vec<String> = stream.map(|b| b.serialize()).collect();
+        assert_eq!(in_order, known.solutions);
+    }
+
+    #[test]
+    fn test_blank_regions() {
+        // x . x .
+        // x . x x
+        // . . x .
+        let mut board = Board::new(4, 3, ' ');
+        board.fill_mask(0b0100_1101_0101, 'x');
+        assert_eq!(
+            board.blank_regions(),
+            vec![0b0011_0010_0010, 0b0000_0000_1000, 0b1000_0000_0000]
+        );
+    }
+
+    #[test]
+    fn test_regions_on_a_single_row() {
+        // The full 64 cells, so shifting by the width would shift every cell out.
+        let mut board = Board::new(64, 1, ' ');
+        board.fill_mask(0xff << 20, 'x');
+        let (left, right) = ((1 << 20) - 1, u64::MAX << 28);
+        assert_eq!(board.blank_regions(), vec![left, right]);
+        assert!(board.is_connected(right));
+        assert!(!board.is_connected(left | right));
+    }
+
+    #[test]
+    fn test_unfillable_region() {
+        let mut board = Board::new(5, 3, ' ');
+        let white = (255, 255, 255);
+        let line = |symbol| {
+            Piece::new(
+                symbol,
+                (0..5).map(|x| (x, 0)).collect(),
+                white,
+                white,
+                false,
+            )
+        };
+        let l = Pieces::get_defaults().into_iter().find(|p| p.symbol == 'L');
+        let mut pieces = [line('I'), line('J'), l.unwrap()];
+        for piece in pieces.iter_mut() {
+            piece.precompute_allowed_placements(&board);
+        }
+        let [i, j, l] = &pieces;
+
+        // Two rows of five can be filled by two lines, but not by a line and an L.
+        board.fill_mask(0b11111, 'x');
+        assert_eq!(
+            board.unfillable_region(&RemainingPieces::new(&[i, j])),
+            None
+        );
+        assert_eq!(
+            board.unfillable_region(&RemainingPieces::new(&[i, l])),
+            Some(Prune::Pair)
+        );
+
+        // One row of five needs a line.
+        board.fill_mask(0b11111 << 5, 'x');
+        assert_eq!(
+            board.unfillable_region(&RemainingPieces::new(&[l, i])),
+            None
+        );
+        assert_eq!(
+            board.unfillable_region(&RemainingPieces::new(&[l])),
+            Some(Prune::Shape)
+        );
+
+        // No pieces have 11 cells between them.
+        let mut board = Board::new(5, 3, ' ');
+        board.fill_mask(0b1111, 'x');
+        assert_eq!(
+            board.unfillable_region(&RemainingPieces::new(&[i, j])),
+            Some(Prune::Size)
+        );
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let pieces = &Pieces::get_defaults();
+        let serialized = "FFmVVVdNwYFFVTTTNNYFUVUTZZNYYUUUTLZNYPPLLLLZZPPPIIIIIx";
+        let board = Board::deserialize(9, 6, serialized, pieces).unwrap();
+        assert_eq!(board.serialize(), serialized);
+
+        let grid = "FFmVVVdNw\nYFFVTTTNN\nYFUVUTZZN\nYYUUUTLZN\nYPPLLLLZZ\nPPPIIIIIx\n";
+        assert_eq!(Board::deserialize(9, 6, grid, pieces).unwrap(), board);
+
+        assert_eq!(
+            Board::deserialize(10, 10, &"F".repeat(100), pieces),
+            Err(ParseError::TooManyCells(100))
+        );
+        assert_eq!(
+            Board::deserialize(9, 6, "FFm", pieces),
+            Err(ParseError::WrongLength {
+                expected: 54,
+                found: 3
+            })
+        );
+        assert_eq!(
+            Board::deserialize(9, 6, &serialized.replace('Y', "?"), pieces),
+            Err(ParseError::UnknownSymbol {
+                symbol: '?',
+                x: 0,
+                y: 1
+            })
+        );
+        assert!(matches!(
+            Board::deserialize(9, 6, &serialized.replacen('I', "F", 1), pieces),
+            Err(ParseError::DisconnectedPiece { symbol: 'F', .. })
+        ));
+
+        // Only the given pieces are known, whichever puzzles have been built before.
+        let _ = crate::puzzle::Puzzle::a_puzzle_a_day();
+        assert_eq!(
+            Board::deserialize(9, 6, serialized, &pieces[1..]),
+            Err(ParseError::UnknownSymbol {
+                symbol: 'F',
+                x: 0,
+                y: 0
+            })
+        );
     }
 }
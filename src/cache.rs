@@ -0,0 +1,97 @@
+use crate::{
+    board::Board,
+    calendar::Day,
+    database::{DatabaseError, SolutionDatabase},
+    puzzle::Puzzle,
+    solve_all::solution_path,
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The database file looked for in the current directory.
+const DEFAULT_DATABASE: &str = "solutions.db";
+
+/// The solutions tree looked for in the current directory.
+const DEFAULT_TREE: &str = "solutions";
+
+/// Precomputed solutions, used to answer without solving.
+pub enum SolutionCache {
+    /// A solution database, read from a file or built into the binary
+    Database(SolutionDatabase),
+    /// A tree of `<Month>/<day>/<Weekday>.txt` solution files
+    Tree(PathBuf, Puzzle),
+}
+
+impl SolutionCache {
+    /// Finds the first available precomputed solutions for a puzzle, looking in order at the given
+    /// database, the database built into the binary, then `solutions.db` and (for the Calendarium
+    /// puzzle only) `solutions/` in the current directory. Only a database that was asked for
+    /// explicitly is an error if it cannot be read, or was built for another puzzle.
+    pub fn find(database: Option<&Path>, puzzle: &Puzzle) -> Result<Option<Self>, DatabaseError> {
+        if let Some(path) = database {
+            return SolutionDatabase::open(path, puzzle)
+                .map(|db| Some(SolutionCache::Database(db)));
+        }
+
+        #[cfg(feature = "embedded-solutions")]
+        if let Ok(db) = SolutionDatabase::embedded(puzzle) {
+            return Ok(Some(SolutionCache::Database(db)));
+        }
+
+        if let Ok(db) = SolutionDatabase::open(Path::new(DEFAULT_DATABASE), puzzle) {
+            return Ok(Some(SolutionCache::Database(db)));
+        }
+
+        // Solution files don't say which puzzle they are for, and a solution can fit a puzzle
+        // without the file having all of that puzzle's solutions (say, with flips allowed),
+        // so the tree is only trusted for the Calendarium puzzle it was written for.
+        let tree = PathBuf::from(DEFAULT_TREE);
+        match tree.is_dir() && puzzle.fingerprint() == Puzzle::calendarium().fingerprint() {
+            true => Ok(Some(SolutionCache::Tree(tree, puzzle.clone()))),
+            false => Ok(None),
+        }
+    }
+
+    /// Gets the number of solutions for a day, or `None` if the day has not been solved.
+    pub fn count_for(&self, day: &Day) -> Option<usize> {
+        match self {
+            SolutionCache::Database(db) => db.count_for(day),
+            SolutionCache::Tree(..) => self.solutions_for(day).map(|s| s.len()),
+        }
+    }
+
+    /// Gets the solutions for a day in `serialize()` order, or `None` if the day has not been solved.
+    pub fn solutions_for(&self, day: &Day) -> Option<Vec<Board>> {
+        match self {
+            SolutionCache::Database(db) => db.solutions_for(day),
+            SolutionCache::Tree(root, puzzle) => {
+                let contents = fs::read_to_string(solution_path(root, day)).ok()?;
+                let mut lines: Vec<&str> = contents.lines().collect();
+                lines.sort();
+                lines
+                    .iter()
+                    .map(|line| solved_board(puzzle, day, line))
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Rebuilds a solved board for a day from its serialized form.
+/// Returns `None` if it does not parse, or does not cover the day's board with the puzzle's pieces.
+fn solved_board(puzzle: &Puzzle, day: &Day, serialized: &str) -> Option<Board> {
+    let board = Board::make(&puzzle.layout, day).ok()?;
+    let solved = Board::deserialize(board.width, board.height, serialized, puzzle.pieces()).ok()?;
+
+    let covers_board = (0..board.height).all(|y| {
+        (0..board.width).all(|x| match board.get(x, y) {
+            Some(symbol) => solved.get(x, y) == Some(symbol),
+            None => solved.is_occupied(x, y),
+        })
+    });
+    let uses_pieces = puzzle
+        .pieces()
+        .iter()
+        .all(|p| solved.mask_of_symbol(p.symbol).count_ones() as usize == p.size());
+    (covers_board && uses_pieces).then_some(solved)
+}
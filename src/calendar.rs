@@ -1,89 +1,274 @@
 use strum_macros::{AsRefStr, Display, EnumIter, EnumString};
 
+use crate::layout::Layout;
 use chrono::Datelike;
 use std::str::FromStr;
+use strum::IntoEnumIterator;
 
-use crate::piece::Coordinates;
-
+/// A date that does not exist, or does not fit the board.
 #[derive(Debug)]
 pub enum DayError {
-    InvalidDay,
+    /// A day past the end of its month, in the year if one was given
+    InvalidDay(Month, MonthDay, Option<i32>),
+    /// A year the calendar cannot handle
+    InvalidYear(i32),
+    /// February 29 in a year without it
+    NotALeapYear(i32),
+    /// No weekday, on a board with weekdays
+    MissingWeekday,
+    /// A weekday, on a board without weekdays
+    UnexpectedWeekday,
+    /// A weekday that does not match the year, with the one that does
+    WrongWeekday(Weekday),
+    /// No year, on a board with years
+    MissingYear,
+    /// A year, on a board without years
+    UnexpectedYear,
+    /// A year the board has no cell for
+    YearNotOnBoard(i32),
+    /// Text that could not be read as a date
+    UnknownDate(String),
+}
+
+impl std::error::Error for DayError {}
+
+impl std::fmt::Display for DayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DayError::InvalidDay(month, _, None) => {
+                write!(f, "{} has only {} days", month, month.day_count())
+            }
+            DayError::InvalidDay(month, _, Some(year)) => {
+                write!(
+                    f,
+                    "{} {} has only {} days",
+                    month,
+                    year,
+                    month.days_in(*year)
+                )
+            }
+            DayError::InvalidYear(year) => write!(f, "{} is outside the calendar", year),
+            DayError::NotALeapYear(year) => {
+                write!(
+                    f,
+                    "{} is not a leap year, so February has only 28 days",
+                    year
+                )
+            }
+            DayError::MissingWeekday => write!(f, "This board needs a weekday"),
+            DayError::UnexpectedWeekday => write!(f, "This board has no weekdays"),
+            DayError::WrongWeekday(weekday) => write!(f, "That day is a {}", weekday),
+            DayError::MissingYear => write!(f, "This board needs a year"),
+            DayError::UnexpectedYear => write!(f, "This board has no years"),
+            DayError::YearNotOnBoard(year) => write!(f, "{} is not on this board", year),
+            DayError::UnknownDate(text) => write!(
+                f,
+                "Could not read \"{}\" as a date, like 2026-10-17, Oct 17, tomorrow or next Friday",
+                text
+            ),
+        }
+    }
 }
 
 /// A struct representing a specific day.
+#[derive(Debug)]
 pub struct Day {
+    /// The month
     pub month: Month,
+    /// The day of the month
     pub day: MonthDay,
-    pub weekday: Weekday,
+    /// `None` for puzzles without weekdays
+    pub weekday: Option<Weekday>,
+    /// `None` for puzzles without years
+    pub year: Option<i32>,
 }
 
 impl Day {
-    /// Creates a new `Day`.
-    pub fn new(month: Month, day: MonthDay, weekday: Weekday) -> Result<Self, DayError> {
-        let valid = match month {
-            Month::April | Month::June | Month::September | Month::November => day <= MonthDay(30),
-            Month::February => day <= MonthDay(29),
-            _ => day <= MonthDay(31),
-        };
-
-        match valid {
+    /// Creates a new `Day`, in no particular year, so February 29 is allowed.
+    pub fn new(month: Month, day: MonthDay, weekday: Option<Weekday>) -> Result<Self, DayError> {
+        match day.value() <= month.day_count() {
             true => Ok(Day {
                 month,
                 day,
                 weekday,
+                year: None,
+            }),
+            false => Err(DayError::InvalidDay(month, day, None)),
+        }
+    }
+
+    /// Creates a `Day` for a date in a specific year, with its weekday.
+    pub fn in_year(year: i32, month: Month, day: MonthDay) -> Result<Self, DayError> {
+        if chrono::NaiveDate::from_ymd_opt(year, 1, 1).is_none() {
+            return Err(DayError::InvalidYear(year));
+        }
+        match chrono::NaiveDate::from_ymd_opt(year, month.clone() as u32 + 1, day.value() as u32) {
+            Some(date) => Day::from_date(date),
+            // February 29 is the only day that is in some years and not others.
+            None if month == Month::February && day.value() == 29 => {
+                Err(DayError::NotALeapYear(year))
+            }
+            None => Err(DayError::InvalidDay(month, day, Some(year))),
+        }
+    }
+
+    /// Creates a `Day` for a calendar date, with its weekday and year.
+    pub fn from_date(date: chrono::NaiveDate) -> Result<Self, DayError> {
+        let month = Month::iter().nth(date.month0() as usize);
+        let day = MonthDay::new(date.day() as u8);
+        let weekday = Weekday::iter().nth(date.weekday().num_days_from_monday() as usize);
+        match (month, day, weekday) {
+            (Some(month), Some(day), Some(weekday)) => Ok(Day {
+                month,
+                day,
+                weekday: Some(weekday),
+                year: Some(date.year()),
             }),
-            false => Err(DayError::InvalidDay),
+            _ => Err(DayError::InvalidYear(date.year())),
+        }
+    }
+
+    /// Creates a `Day` for today's date, in the local time zone.
+    pub fn today() -> Result<Self, DayError> {
+        Day::from_date(chrono::Local::now().date_naive())
+    }
+
+    /// Reads a date written as `2026-10-17`, `Oct 17`, `17 October 2027`, `today`, `tomorrow`,
+    /// `yesterday`, `friday`, `next friday` or `last friday`, relative to `today`.
+    /// A month and day without a year are in the same year as `today`.
+    pub fn parse(text: &str, today: chrono::NaiveDate) -> Result<Self, DayError> {
+        let unknown = || DayError::UnknownDate(text.to_string());
+        if let Ok(date) = chrono::NaiveDate::parse_from_str(text.trim(), "%Y-%m-%d") {
+            return Day::from_date(date);
+        }
+
+        let lowercase = text.to_lowercase();
+        let words: Vec<&str> = lowercase
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|word| !word.is_empty())
+            .collect();
+        let days_ahead = match words.as_slice() {
+            ["today"] => 0,
+            ["tomorrow"] => 1,
+            ["yesterday"] => -1,
+            [weekday] => days_until(today, weekday).ok_or_else(unknown)?,
+            ["next", weekday] => match days_until(today, weekday).ok_or_else(unknown)? {
+                0 => 7,
+                days => days,
+            },
+            ["last", weekday] => days_until(today, weekday).ok_or_else(unknown)? - 7,
+            _ => return Day::parse_month_day(&words, today.year()).ok_or_else(unknown)?,
+        };
+        today
+            .checked_add_signed(chrono::Duration::days(days_ahead))
+            .ok_or(DayError::InvalidYear(today.year()))
+            .and_then(Day::from_date)
+    }
+
+    /// Reads a month name and a day of the month, in either order, with an optional year after them.
+    fn parse_month_day(words: &[&str], this_year: i32) -> Option<Result<Self, DayError>> {
+        let (month, day, year) = match words {
+            [first, second] => (first, second, None),
+            [first, second, year] => (first, second, Some(year)),
+            _ => return None,
+        };
+        let (month, day) = match named(Month::iter(), month) {
+            Some(month) => (month, day),
+            None => (named(Month::iter(), day)?, month),
+        };
+        let day = MonthDay::from_str(day).ok()?;
+        let year = match year {
+            Some(year) => year.parse().ok()?,
+            None => this_year,
+        };
+        Some(Day::in_year(year, month, day))
+    }
+
+    /// Leaves out the weekday and year if the layout has no cells for them.
+    pub fn for_layout(mut self, layout: &Layout) -> Self {
+        if !layout.has_weekdays() {
+            self.weekday = None;
+        }
+        if !layout.has_years() {
+            self.year = None;
+        }
+        self
+    }
+
+    /// Checks that the day has exactly the parts the layout has cells for.
+    pub fn check_layout(&self, layout: &Layout) -> Result<(), DayError> {
+        match (&self.weekday, layout.has_weekdays()) {
+            (None, true) => return Err(DayError::MissingWeekday),
+            (Some(_), false) => return Err(DayError::UnexpectedWeekday),
+            _ => {}
+        }
+        match (self.year, layout.has_years()) {
+            (None, true) => Err(DayError::MissingYear),
+            (Some(_), false) => Err(DayError::UnexpectedYear),
+            (Some(year), true) if layout.year_cell(year).is_none() => {
+                Err(DayError::YearNotOnBoard(year))
+            }
+            _ => Ok(()),
         }
     }
+}
+
+/// Finds the month or weekday a word names, in full or by its first three letters or more.
+fn named<T: AsRef<str>>(mut names: impl Iterator<Item = T>, word: &str) -> Option<T> {
+    names.find(|name| word.len() >= 3 && name.as_ref().to_lowercase().starts_with(word))
+}
+
+/// Gets the number of days from `today` to the next time it is the named weekday, from 0 to 6.
+fn days_until(today: chrono::NaiveDate, word: &str) -> Option<i64> {
+    let weekday = named(Weekday::iter(), word)? as i64;
+    let today = today.weekday().num_days_from_monday() as i64;
+    Some((weekday - today).rem_euclid(7))
+}
 
-    pub fn today() -> Self {
-        let now = chrono::Local::now();
-        match Day::new(
-            Month::from_str(&now.format("%B").to_string()).unwrap(),
-            MonthDay::new(now.day() as u8).unwrap(),
-            Weekday::from_str(&now.format("%A").to_string()).unwrap(),
-        ) {
-            Ok(day) => day,
-            Err(_) => panic!("Invalid day."),
+impl std::fmt::Display for Day {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if let Some(weekday) = &self.weekday {
+            write!(f, "{}, ", weekday)?;
+        }
+        write!(f, "{} {}", self.month, self.day)?;
+        if let Some(year) = self.year {
+            write!(f, ", {}", year)?;
         }
+        Ok(())
     }
 }
 
 /// Represents the months of the year.
-#[derive(EnumIter, EnumString, Display, AsRefStr, Clone)]
+#[derive(EnumIter, EnumString, Display, AsRefStr, Clone, Debug, PartialEq)]
 pub enum Month {
+    /// January, with 31 days
     January,
+    /// February, with 29 days (28 outside leap years)
     February,
+    /// March, with 31 days
     March,
+    /// April, with 30 days
     April,
+    /// May, with 31 days
     May,
+    /// June, with 30 days
     June,
+    /// July, with 31 days
     July,
+    /// August, with 31 days
     August,
+    /// September, with 30 days
     September,
+    /// October, with 31 days
     October,
+    /// November, with 30 days
     November,
+    /// December, with 31 days
     December,
 }
 
 impl Month {
-    pub fn to_coordinates(&self) -> Coordinates {
-        match self {
-            Month::January => (0, 0),
-            Month::February => (1, 0),
-            Month::March => (2, 0),
-            Month::April => (3, 0),
-            Month::May => (0, 1),
-            Month::June => (0, 2),
-            Month::July => (0, 3),
-            Month::August => (0, 4),
-            Month::September => (0, 5),
-            Month::October => (1, 5),
-            Month::November => (2, 5),
-            Month::December => (3, 5),
-        }
-    }
-
+    /// Gets the most days the month can have, so 29 for February.
     pub fn day_count(&self) -> u8 {
         match self {
             Month::January
@@ -97,38 +282,42 @@ impl Month {
             Month::February => 29,
         }
     }
+
+    /// Gets the number of days the month has in a year, so 28 for February outside leap years.
+    pub fn days_in(&self, year: i32) -> u8 {
+        let leap = chrono::NaiveDate::from_ymd_opt(year, 2, 29).is_some();
+        match self {
+            Month::February if !leap => 28,
+            _ => self.day_count(),
+        }
+    }
 }
 
 /// Represents the days of the week.
 #[derive(EnumIter, EnumString, Display, AsRefStr, Clone, Debug, PartialEq)]
 pub enum Weekday {
+    /// Monday
     Monday,
+    /// Tuesday
     Tuesday,
+    /// Wednesday
     Wednesday,
+    /// Thursday
     Thursday,
+    /// Friday
     Friday,
+    /// Saturday
     Saturday,
+    /// Sunday
     Sunday,
 }
 
-impl Weekday {
-    pub fn to_coordinates(&self) -> Coordinates {
-        match self {
-            Weekday::Monday => (7, 0),
-            Weekday::Tuesday => (8, 0),
-            Weekday::Wednesday => (7, 1),
-            Weekday::Thursday => (7, 2),
-            Weekday::Friday => (7, 3),
-            Weekday::Saturday => (8, 3),
-            Weekday::Sunday => (8, 4),
-        }
-    }
-}
-
+/// A day of the month, from 1 to 31.
 #[derive(Debug, PartialEq, Eq, PartialOrd, Clone)]
 pub struct MonthDay(u8);
 
 impl MonthDay {
+    /// Creates a day of the month, or `None` if it is not between 1 and 31.
     pub fn new(day: u8) -> Option<Self> {
         if day > 0 && day <= 31 {
             Some(MonthDay(day))
@@ -137,47 +326,18 @@ impl MonthDay {
         }
     }
 
-    pub fn to_coordinates(&self) -> Coordinates {
-        match self.0 {
-            1 => (4, 0),
-            2 => (5, 0),
-            3 => (6, 0),
-            4 => (1, 1),
-            5 => (2, 1),
-            6 => (3, 1),
-            7 => (4, 1),
-            8 => (5, 1),
-            9 => (6, 1),
-            10 => (1, 2),
-            11 => (2, 2),
-            12 => (3, 2),
-            13 => (4, 2),
-            14 => (7, 5), // Weird one
-            15 => (6, 2),
-            16 => (1, 3),
-            17 => (2, 3),
-            18 => (3, 3),
-            19 => (4, 3),
-            20 => (5, 3),
-            21 => (6, 3),
-            22 => (1, 4),
-            23 => (2, 4),
-            24 => (3, 4),
-            25 => (4, 4),
-            26 => (5, 4),
-            27 => (6, 4),
-            28 => (4, 5),
-            29 => (5, 5),
-            30 => (6, 5),
-            31 => (5, 2), // Weird one
-            _ => unreachable!(),
-        }
+    /// Gets the day of the month as a number.
+    pub fn value(&self) -> u8 {
+        self.0
     }
 }
 
+/// Text that could not be read as a day of the month.
 #[derive(Debug)]
 pub enum MonthDayError {
+    /// Text that is not a number
     InvalidFormat,
+    /// A number that is not between 1 and 31
     OutOfRange,
 }
 
@@ -197,7 +357,7 @@ impl FromStr for MonthDay {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let day = s.parse::<u8>().map_err(|_| MonthDayError::InvalidFormat)?;
-        if day < 1 || day > 31 {
+        if !(1..=31).contains(&day) {
             Err(MonthDayError::OutOfRange)
         } else {
             Ok(MonthDay(day))
@@ -235,4 +395,83 @@ mod tests {
         assert_eq!(Weekday::from_str("Sunday").unwrap(), Weekday::Sunday);
         assert!(Weekday::from_str("Foo").is_err());
     }
+
+    #[test]
+    fn test_day_in_year() {
+        let day = Day::in_year(2026, Month::October, MonthDay(17)).unwrap();
+        assert_eq!(day.weekday, Some(Weekday::Saturday));
+        assert_eq!(day.to_string(), "Saturday, October 17, 2026");
+        assert!(matches!(
+            Day::in_year(2025, Month::February, MonthDay(29)),
+            Err(DayError::NotALeapYear(2025))
+        ));
+        assert!(Day::in_year(2024, Month::February, MonthDay(29)).is_ok());
+        let error = Day::in_year(2024, Month::February, MonthDay(30)).unwrap_err();
+        assert_eq!(error.to_string(), "February 2024 has only 29 days");
+        let error = Day::in_year(2025, Month::February, MonthDay(30)).unwrap_err();
+        assert_eq!(error.to_string(), "February 2025 has only 28 days");
+        let error = Day::in_year(2025, Month::April, MonthDay(31)).unwrap_err();
+        assert_eq!(error.to_string(), "April 2025 has only 30 days");
+        assert!(matches!(
+            Day::in_year(1_000_000, Month::March, MonthDay(1)),
+            Err(DayError::InvalidYear(1_000_000))
+        ));
+    }
+
+    #[test]
+    fn test_day_new() {
+        assert!(Day::new(Month::February, MonthDay(29), None).is_ok());
+        let error = Day::new(Month::February, MonthDay(30), None).unwrap_err();
+        assert_eq!(error.to_string(), "February has only 29 days");
+        assert!(Day::new(Month::April, MonthDay(31), Some(Weekday::Monday)).is_err());
+        assert!(Day::new(Month::December, MonthDay(31), None).is_ok());
+    }
+
+    #[test]
+    fn test_day_parse() {
+        let today = chrono::NaiveDate::from_ymd_opt(2026, 10, 17).unwrap(); // A Saturday
+        let parse = |text| Day::parse(text, today).map(|day| day.to_string());
+        assert_eq!(parse("2027-02-14").unwrap(), "Sunday, February 14, 2027");
+        assert_eq!(parse("Oct 17").unwrap(), "Saturday, October 17, 2026");
+        assert_eq!(
+            parse("17 october, 2027").unwrap(),
+            "Sunday, October 17, 2027"
+        );
+        assert_eq!(parse("today").unwrap(), "Saturday, October 17, 2026");
+        assert_eq!(parse("Tomorrow").unwrap(), "Sunday, October 18, 2026");
+        assert_eq!(parse("yesterday").unwrap(), "Friday, October 16, 2026");
+        assert_eq!(parse("friday").unwrap(), "Friday, October 23, 2026");
+        assert_eq!(parse("saturday").unwrap(), "Saturday, October 17, 2026");
+        assert_eq!(parse("next sat").unwrap(), "Saturday, October 24, 2026");
+        assert_eq!(parse("last Friday").unwrap(), "Friday, October 16, 2026");
+        assert!(matches!(
+            Day::parse("Feb 29", today),
+            Err(DayError::NotALeapYear(2026))
+        ));
+        assert!(matches!(
+            Day::parse("Feb 30 2028", today),
+            Err(DayError::InvalidDay(Month::February, _, Some(2028)))
+        ));
+        for text in ["2026-13-01", "ju 4", "June", "next month", "", "fr"] {
+            assert!(matches!(
+                Day::parse(text, today),
+                Err(DayError::UnknownDate(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_day_check_layout() {
+        let day = Day::in_year(2026, Month::October, MonthDay(17)).unwrap();
+        assert!(matches!(
+            day.check_layout(&Layout::calendarium()),
+            Err(DayError::UnexpectedYear)
+        ));
+        let day = day.for_layout(&Layout::calendarium());
+        assert!(day.check_layout(&Layout::calendarium()).is_ok());
+        assert!(matches!(
+            day.check_layout(&Layout::a_puzzle_a_day()),
+            Err(DayError::UnexpectedWeekday)
+        ));
+    }
 }
@@ -2,16 +2,122 @@ use dialoguer::{theme::ColorfulTheme, Select};
 use std::str::FromStr;
 use strum::IntoEnumIterator;
 
-use crate::{
-    board::Board,
-    calendar::{Day, Month, MonthDay, Weekday},
-    piece::{Piece, Placement, Rotation},
+use calendar_puzzle::{
+    calendar::{Day, DayError, Month, MonthDay, Weekday},
+    error::Error,
+    layout::Layout,
+    piece::{dimensions_of, Piece},
+    solver::{Solver, Strategy},
+    stats::SearchStats,
 };
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
+use colored::Colorize;
+use std::io::{self, IsTerminal};
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+/// The built-in puzzles
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum PuzzleName {
+    /// Calendarium: a 9×6 board with months, days and weekdays, and ten pieces
+    #[default]
+    Calendarium,
+    /// DragonFjord's A-Puzzle-A-Day: a 7×7 board with months and days, and eight pieces
+    APuzzleADay,
+}
+
+impl PuzzleName {
+    /// Gets the directory of solution files for the puzzle, unless another one is given.
+    /// Each puzzle has its own, since their files are laid out differently.
+    pub fn solutions_dir(&self) -> PathBuf {
+        match self {
+            PuzzleName::Calendarium => PathBuf::from("solutions"),
+            PuzzleName::APuzzleADay => PathBuf::from("solutions/a-puzzle-a-day"),
+        }
+    }
+}
+
+/// The solvers, as they are chosen on the command line
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum SolverName {
+    /// Parallel backtracking, placing pieces one at a time
+    #[default]
+    Backtrack,
+    /// Knuth's Dancing Links (Algorithm X) exact cover solver
+    Dlx,
+}
+
+impl From<SolverName> for Solver {
+    fn from(name: SolverName) -> Self {
+        match name {
+            SolverName::Backtrack => Solver::Backtrack,
+            SolverName::Dlx => Solver::Dlx,
+        }
+    }
+}
+
+/// The backtracking strategies, as they are chosen on the command line
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum StrategyName {
+    /// Place the pieces one at a time, trying every placement of each
+    #[default]
+    PieceFirst,
+    /// Fill the open cell with the fewest placements left, trying every piece that covers it
+    CellFirst,
+}
+
+impl From<StrategyName> for Strategy {
+    fn from(name: StrategyName) -> Self {
+        match name {
+            StrategyName::PieceFirst => Strategy::PieceFirst,
+            StrategyName::CellFirst => Strategy::CellFirst,
+        }
+    }
+}
+
+/// Commands other than solving a single date
+#[derive(Subcommand)]
+pub enum Command {
+    /// Solve every date, writing all solutions to <Month>/<day>/<Weekday>.txt files
+    SolveAll {
+        /// The directory to write solutions to [default: solutions, or solutions/a-puzzle-a-day]
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Convert a tree of solution files into a single solution database file
+    ImportSolutions {
+        /// The directory of <Month>/<day>/<Weekday>.txt solution files [default: solutions, or solutions/a-puzzle-a-day]
+        #[arg(long)]
+        from: Option<PathBuf>,
+        /// The database file to write
+        #[arg(long, default_value = "solutions.db")]
+        to: PathBuf,
+    },
+    /// Check that every solution file is a valid, unique solution for its date
+    Verify {
+        /// The directory of <Month>/<day>/<Weekday>.txt solution files [default: solutions, or solutions/a-puzzle-a-day]
+        #[arg(long)]
+        from: Option<PathBuf>,
+    },
+    /// Time the piece-first and cell-first backtracking strategies on every date
+    Benchmark {
+        /// Only time every <EVERY>th date
+        #[arg(long, default_value_t = 1)]
+        every: usize,
+    },
+}
 
 /// Command-line arguments
 #[derive(Parser)]
 pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// The date to solve, like 2026-10-17, Oct 17, tomorrow or next friday
+    #[arg(num_args = 1.., conflicts_with_all = ["today", "month", "day", "weekday", "year"])]
+    pub date: Vec<String>,
+
     /// Show all solutions, not just the first one.
     #[arg(long)]
     pub all: bool,
@@ -21,7 +127,7 @@ pub struct Args {
     pub show_pieces: bool,
 
     /// Use today's date.
-    #[arg(long)]
+    #[arg(long, conflicts_with_all = ["month", "day", "weekday", "year"])]
     pub today: bool,
 
     /// Use raw text output
@@ -29,94 +135,312 @@ pub struct Args {
     pub raw: bool,
 
     /// The month
-    #[arg(long)]
+    #[arg(long, requires = "day")]
     pub month: Option<Month>,
 
     /// The day of the month
-    #[arg(long)]
+    #[arg(long, requires = "month")]
     pub day: Option<MonthDay>,
 
     /// The day of the week
-    #[arg(long)]
+    #[arg(long, requires = "month")]
     pub weekday: Option<Weekday>,
 
+    /// The year, which checks for leap years and decides the weekday
+    #[arg(long, requires = "month")]
+    pub year: Option<i32>,
+
     /// Hint <HINT> pieces instead of giving full solutions
     #[arg(long)]
     pub hint: Option<usize>,
+
+    /// Only count the solutions, without showing them
+    #[arg(long)]
+    pub count: bool,
+
+    /// With --count, break the count down by placement of the first piece
+    #[arg(long, requires = "count")]
+    pub breakdown: bool,
+
+    /// Stop solving after this long (like 500ms, 30s or 2m), showing the solutions found so far
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration, conflicts_with = "count")]
+    pub timeout: Option<Duration>,
+
+    /// Show progress while solving, and statistics about the search afterwards, on stderr
+    #[arg(long, conflicts_with = "count")]
+    pub stats: bool,
+
+    /// Always give the same solutions, in the same order (the lexicographically smallest first)
+    #[arg(long)]
+    pub deterministic: bool,
+
+    /// Look up solutions in this solution database before solving
+    #[arg(long, value_name = "FILE")]
+    pub database: Option<PathBuf>,
+
+    /// Always solve, instead of looking up precomputed solutions
+    #[arg(long = "no-cache", conflicts_with = "database")]
+    pub no_cache: bool,
+
+    /// The puzzle to solve
+    #[arg(long, value_enum, global = true, default_value_t = PuzzleName::Calendarium)]
+    pub puzzle: PuzzleName,
+
+    /// Use the board layout in this file instead of the puzzle's own board
+    #[arg(long, value_name = "FILE", global = true)]
+    pub layout: Option<PathBuf>,
+
+    /// Let every piece be flipped over, as if the pieces were two-sided
+    #[arg(long = "allow-flips", global = true)]
+    pub allow_flips: bool,
+
+    /// Use the pieces in this file instead of the puzzle's own pieces
+    #[arg(long, value_name = "FILE", global = true)]
+    pub pieces: Option<PathBuf>,
+
+    /// The solver to use
+    #[arg(long, value_enum, global = true, default_value_t = SolverName::Backtrack)]
+    pub solver: SolverName,
+
+    /// The search order of the backtracking solver
+    #[arg(long, value_enum, global = true, default_value_t = StrategyName::PieceFirst)]
+    pub strategy: StrategyName,
 }
 
 impl Args {
-    pub fn get_day(&self) -> Day {
-        if self.today {
-            Day::today()
-        } else if let (Some(month), Some(day), Some(weekday)) = (
-            self.month.as_ref(),
-            self.day.as_ref(),
-            self.weekday.as_ref(),
-        ) {
-            Day::new(month.clone(), day.clone(), weekday.clone()).unwrap()
+    /// Gets the day to solve on a layout, checking that it has exactly the parts the layout has cells for.
+    pub fn get_day(&self, layout: &Layout) -> Result<Day, Error> {
+        let day = if self.today {
+            Day::today()?.for_layout(layout)
+        } else if !self.date.is_empty() {
+            let today = chrono::Local::now().date_naive();
+            Day::parse(&self.date.join(" "), today)?.for_layout(layout)
+        } else if let (Some(month), Some(day)) = (self.month.as_ref(), self.day.as_ref()) {
+            match self.year {
+                Some(year) => {
+                    // The year decides the weekday, so a given weekday has to agree with it.
+                    let mut day = Day::in_year(year, month.clone(), day.clone())?;
+                    match &self.weekday {
+                        Some(weekday) if day.weekday.as_ref() != Some(weekday) => {
+                            return Err(DayError::WrongWeekday(day.weekday.unwrap()).into());
+                        }
+                        None if !layout.has_weekdays() => day.weekday = None,
+                        _ => {}
+                    }
+                    // On a board without years, the year only checks the date and decides the weekday.
+                    if !layout.has_years() {
+                        day.year = None;
+                    }
+                    day
+                }
+                None => Day::new(month.clone(), day.clone(), self.weekday.clone())?,
+            }
         } else {
-            select_day()
-        }
+            select_day(layout)?
+        };
+
+        day.check_layout(layout)?;
+        Ok(day)
     }
 }
 
-/// Gets input from the user to create a `Day` struct
-pub fn select_day() -> Day {
-    // Choose a month
-    let months = Month::iter().map(|m| m.to_string()).collect::<Vec<_>>();
-    let month_index = Select::with_theme(&ColorfulTheme::default())
-        .with_prompt("Select a month")
-        .items(&months)
-        .default(0)
-        .interact()
-        .unwrap();
-    let month = Month::from_str(&months[month_index]).unwrap();
+/// Gets the exit status for an error: 1 if there is no solution, 2 for invalid input,
+/// 3 if the solver hit a limit, 4 if a file or the solution database failed,
+/// 5 if the benchmark strategies disagreed, 6 if the search panicked,
+/// and 7 if `verify` found dates without a solutions file.
+pub fn exit_code(error: &Error) -> i32 {
+    match error {
+        Error::NoSolution => 1,
+        Error::InvalidDate(_)
+        | Error::Layout(..)
+        | Error::PieceSet(..)
+        | Error::PiecesDoNotFit(_)
+        | Error::Placement(_)
+        | Error::Parse(_)
+        | Error::InvalidHint(..)
+        | Error::StatsNeedBacktrack
+        | Error::Prompt(_)
+        | Error::InvalidSolutions(_) => 2,
+        Error::TimedOut(_) => 3,
+        Error::Io(..) | Error::Database(_) => 4,
+        Error::StrategiesDisagree(_) => 5,
+        Error::SearchPanicked => 6,
+        Error::MissingSolutions(_) => 7,
+    }
+}
 
-    let day_count = month.day_count();
+/// Parses a duration such as `500ms`, `30s`, `1.5m` or `2h`. A bare number is in seconds.
+fn parse_duration(text: &str) -> Result<Duration, String> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("'{}' is not a duration, like 500ms, 30s or 2m", text))?;
+    let seconds = match unit {
+        "ms" => number / 1000.0,
+        "" | "s" => number,
+        "m" => number * 60.0,
+        "h" => number * 3600.0,
+        _ => return Err(format!("Unknown unit '{}', use ms, s, m or h", unit)),
+    };
+    Duration::try_from_secs_f64(seconds).map_err(|e| e.to_string())
+}
 
-    // Choose a month day
-    let month_day_index = Select::with_theme(&ColorfulTheme::default())
-        .with_prompt("Select a day of the month")
-        .items(&(1..=day_count).map(|d| d.to_string()).collect::<Vec<_>>())
+/// Asks the user to pick one of `items`, giving its index
+fn choose<T: ToString>(prompt: &str, items: &[T]) -> Result<usize, Error> {
+    Select::with_theme(&ColorfulTheme::default())
+        .with_prompt(prompt)
+        .items(items)
         .default(0)
         .interact()
-        .unwrap();
+        .map_err(Error::Prompt)
+}
 
-    let day = MonthDay::new((month_day_index + 1) as u8).expect("Invalid day of the month");
+/// Gets input from the user to create a `Day` struct, asking for the parts the layout has cells for
+pub fn select_day(layout: &Layout) -> Result<Day, Error> {
+    // Without a terminal the prompts would wait for keys that never come.
+    if !io::stdin().is_terminal() {
+        let not_a_terminal = io::Error::new(
+            io::ErrorKind::NotConnected,
+            "standard input is not a terminal",
+        );
+        return Err(Error::Prompt(not_a_terminal));
+    }
 
-    // Choose a day of the week
-    let weekdays = Weekday::iter().map(|w| w.to_string()).collect::<Vec<_>>();
-    let weekday_index = Select::with_theme(&ColorfulTheme::default())
-        .with_prompt("Select a day of the week")
-        .items(&weekdays)
-        .default(0)
-        .interact()
-        .unwrap();
-    let weekday = Weekday::from_str(&weekdays[weekday_index]).unwrap();
-
-    match Day::new(month, day, weekday) {
-        Ok(day) => day,
-        Err(e) => {
-            println!("Error: {:?}", e);
-            std::process::exit(1);
+    // Choose a year
+    let year = match layout.has_years() {
+        true => {
+            let years = layout.years();
+            Some(years[choose("Select a year", &years)?])
         }
-    }
+        false => None,
+    };
+
+    // Choose a month
+    let months = Month::iter().map(|m| m.to_string()).collect::<Vec<_>>();
+    let month_index = choose("Select a month", &months)?;
+    let month = Month::from_str(&months[month_index]).unwrap();
+
+    // Choose a month day
+    let days = (1..=month.day_count())
+        .filter_map(MonthDay::new)
+        .collect::<Vec<_>>();
+    let day = days[choose("Select a day of the month", &days)?].clone();
+
+    // The year decides the weekday, so only ask for one without a year.
+    let day = match year {
+        Some(year) => Day::in_year(year, month, day).map(|day| day.for_layout(layout)),
+        None => {
+            // Choose a day of the week
+            let weekday = match layout.has_weekdays() {
+                true => {
+                    let weekdays = Weekday::iter().map(|w| w.to_string()).collect::<Vec<_>>();
+                    let weekday_index = choose("Select a day of the week", &weekdays)?;
+                    Some(Weekday::from_str(&weekdays[weekday_index]).unwrap())
+                }
+                false => None,
+            };
+            Day::new(month, day, weekday)
+        }
+    };
+    Ok(day?)
 }
 
-/// Shows pieces for placement
+/// Shows pieces for placement, in every orientation they can be placed in
 pub fn show_pieces(pieces: &[Piece]) {
     println!("Pieces to place:");
     for piece in pieces {
-        // Make an example board just big enough to display this piece.
-        let mut example_board = Board::new(
-            piece.get_dimensions_of_shape(Rotation::Zero, false).0 as usize,
-            piece.get_dimensions_of_shape(Rotation::Zero, false).1 as usize,
-            ' ',
+        // Draw the orientations side by side, one row of cells at a time.
+        let orientations = piece.orientations();
+        let height = orientations
+            .iter()
+            .map(|shape| dimensions_of(shape).1)
+            .max()
+            .unwrap_or(0);
+        for y in 0..height {
+            let row: Vec<String> = orientations
+                .iter()
+                .map(|shape| {
+                    (0..dimensions_of(shape).0)
+                        .map(|x| match shape.contains(&(x, y)) {
+                            true => format!(" {} ", piece.display_symbol)
+                                .color(piece.color)
+                                .on_color(piece.bg)
+                                .to_string(),
+                            false => "   ".to_string(),
+                        })
+                        .collect()
+                })
+                .collect();
+            println!("{}", row.join("   "));
+        }
+
+        let symmetry = piece.symmetry();
+        let flipping = match (symmetry.is_chiral(), piece.flippable) {
+            (false, _) => "the same when flipped over",
+            (true, true) => "flippable",
+            (true, false) => "one-sided",
+        };
+        println!(
+            "{} orientations, {}, {}",
+            piece.orientations().len(),
+            symmetry,
+            flipping
         );
-        // Place the piece in the top-left corner for display
-        example_board.place_piece(piece, Placement::new(Rotation::Zero, false, (0, 0)));
-        example_board.display();
         println!();
     }
 }
+
+/// Prints statistics about a search that took `elapsed` on stderr
+pub fn print_stats(stats: &SearchStats, elapsed: Duration) {
+    let count = |counter: &std::sync::atomic::AtomicUsize| counter.load(Ordering::Relaxed);
+    eprintln!("Nodes visited: {}", stats.nodes());
+    eprintln!("Placements tried: {}", count(&stats.placements));
+    eprintln!(
+        "Placements pruned: {} ({} by size, {} by shape, {} by pair)",
+        stats.pruned(),
+        count(&stats.pruned_by_size),
+        count(&stats.pruned_by_shape),
+        count(&stats.pruned_by_pair)
+    );
+    eprintln!("Solutions found: {}", count(&stats.solutions));
+    eprintln!("Wall time: {:.3}s", elapsed.as_secs_f64());
+
+    eprintln!("Depth  Nodes  Branching");
+    for (depth, (nodes, branching)) in stats.branching_by_depth().iter().enumerate() {
+        eprintln!("{:>5}  {:>5}  {:>9.2}", depth, nodes, branching);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("500ms"), Ok(Duration::from_millis(500)));
+        assert_eq!(parse_duration("30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("30"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("1.5m"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("2h"), Ok(Duration::from_secs(7200)));
+        assert!(parse_duration("soon").is_err());
+        assert!(parse_duration("3 days").is_err());
+    }
+
+    #[test]
+    fn test_exit_code() {
+        assert_eq!(exit_code(&Error::NoSolution), 1);
+        assert_eq!(exit_code(&Error::InvalidDate(DayError::MissingWeekday)), 2);
+        assert_eq!(exit_code(&Error::InvalidHint(12, 9)), 2);
+        assert_eq!(exit_code(&Error::StatsNeedBacktrack), 2);
+        assert_eq!(exit_code(&Error::TimedOut(Duration::from_secs(1))), 3);
+        let missing = std::io::Error::from(std::io::ErrorKind::NotFound);
+        assert_eq!(exit_code(&Error::Io(PathBuf::from("a.txt"), missing)), 4);
+        assert_eq!(exit_code(&Error::StrategiesDisagree(1)), 5);
+        assert_eq!(exit_code(&Error::SearchPanicked), 6);
+        assert_eq!(exit_code(&Error::MissingSolutions(366)), 7);
+    }
+}
@@ -0,0 +1,377 @@
+use crate::{
+    board::Board,
+    calendar::Day,
+    layout::Layout,
+    puzzle::Puzzle,
+    solve_all::{all_days, solution_path},
+};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// File format
+///
+/// All integers are little-endian.
+///
+/// | Size          | Contents                                                  |
+/// |---------------|-----------------------------------------------------------|
+/// | 4             | Magic bytes `CPDB`                                        |
+/// | 1             | Format version                                            |
+/// | 8             | Fingerprint of the puzzle the solutions are for           |
+/// | 1, 1          | Board width and height                                    |
+/// | 1             | Number of pieces `P`                                      |
+/// | 1             | Bytes per placement id `B` (1 or 2)                       |
+/// | P             | Piece symbols, in the order they are stored in solutions  |
+/// | 8 × I         | Per-date index: first solution (u32), solution count (u32) |
+/// | P × B × N     | Solutions, each a placement id for every piece            |
+///
+/// A placement id is the rank of the placement's occupancy mask among the sorted, distinct
+/// masks the piece can take on an empty board, so it does not depend on the date.
+/// Dates that are not in the database have a first solution of `u32::MAX`.
+/// Puzzles without weekdays store each date in the slot after Sunday.
+/// The index has `YEAR_INDEX_LEN` slots for each year on the board, or just one set without years.
+const MAGIC: &[u8; 4] = b"CPDB";
+const VERSION: u8 = 3;
+const HEADER_LEN: usize = 17;
+const YEAR_INDEX_LEN: usize = 12 * 31 * 8; // Every month, day of the month and weekday (or none)
+const ABSENT: u32 = u32::MAX;
+
+/// A solution database that could not be read or built.
+#[derive(Debug)]
+pub enum DatabaseError {
+    /// The file could not be read
+    Io(io::Error),
+    /// The file is not a solution database
+    BadMagic,
+    /// A database format this version cannot read
+    UnsupportedVersion(u8),
+    /// The file ends before the data it says it has
+    Truncated,
+    /// The database was built for another layout or piece set
+    BoardMismatch,
+    /// A placement the piece with this symbol cannot have
+    UnknownPlacement(char),
+    /// A solution that does not fit the board
+    InvalidSolution(String),
+}
+
+impl std::error::Error for DatabaseError {}
+
+impl std::fmt::Display for DatabaseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DatabaseError::Io(e) => write!(f, "{}", e),
+            DatabaseError::BadMagic => write!(f, "Not a solution database"),
+            DatabaseError::UnsupportedVersion(v) => write!(f, "Unsupported version {}", v),
+            DatabaseError::Truncated => write!(f, "The database is truncated"),
+            DatabaseError::BoardMismatch => {
+                write!(f, "The database was built for a different puzzle")
+            }
+            DatabaseError::UnknownPlacement(symbol) => {
+                write!(f, "Piece {} has a placement that is not allowed", symbol)
+            }
+            DatabaseError::InvalidSolution(s) => write!(f, "Invalid solution: {}", s),
+        }
+    }
+}
+
+impl From<io::Error> for DatabaseError {
+    fn from(e: io::Error) -> Self {
+        DatabaseError::Io(e)
+    }
+}
+
+/// A read-only database of precomputed solutions for every date.
+pub struct SolutionDatabase {
+    bytes: Vec<u8>,
+    puzzle: Puzzle,
+    symbols: Vec<char>,
+    placements: Vec<Vec<u64>>, // Distinct placement masks of each piece, indexed by placement id
+    id_bytes: usize,
+}
+
+impl SolutionDatabase {
+    /// Loads a database of solutions for a puzzle from a file.
+    pub fn open(path: &Path, puzzle: &Puzzle) -> Result<Self, DatabaseError> {
+        Self::from_bytes(fs::read(path)?, puzzle)
+    }
+
+    /// Loads the database built into the binary from `solutions.db`, if it is for the puzzle.
+    #[cfg(feature = "embedded-solutions")]
+    pub fn embedded(puzzle: &Puzzle) -> Result<Self, DatabaseError> {
+        Self::from_bytes(include_bytes!("../solutions.db").to_vec(), puzzle)
+    }
+
+    /// Loads a database from its bytes, checking its header against the puzzle.
+    pub fn from_bytes(bytes: Vec<u8>, puzzle: &Puzzle) -> Result<Self, DatabaseError> {
+        if bytes.len() < HEADER_LEN {
+            return Err(DatabaseError::Truncated);
+        }
+        if &bytes[0..4] != MAGIC {
+            return Err(DatabaseError::BadMagic);
+        }
+        if bytes[4] != VERSION {
+            return Err(DatabaseError::UnsupportedVersion(bytes[4]));
+        }
+
+        let fingerprint = u64::from_le_bytes(bytes[5..13].try_into().unwrap());
+        let (width, height) = (bytes[13] as usize, bytes[14] as usize);
+        let piece_count = bytes[15] as usize;
+        let id_bytes = bytes[16] as usize;
+        let layout = &puzzle.layout;
+        if fingerprint != puzzle.fingerprint() || (width, height) != (layout.width, layout.height) {
+            return Err(DatabaseError::BoardMismatch);
+        }
+        let symbols: Vec<char> = bytes
+            .get(HEADER_LEN..HEADER_LEN + piece_count)
+            .ok_or(DatabaseError::Truncated)?
+            .iter()
+            .map(|&b| b as char)
+            .collect();
+
+        let placements = placement_table(puzzle);
+        let known: Vec<char> = placements.iter().map(|(symbol, _)| *symbol).collect();
+        if symbols != known || !(1..=2).contains(&id_bytes) {
+            return Err(DatabaseError::BoardMismatch);
+        }
+
+        let database = SolutionDatabase {
+            bytes,
+            puzzle: puzzle.clone(),
+            symbols,
+            placements: placements.into_iter().map(|(_, masks)| masks).collect(),
+            id_bytes,
+        };
+
+        // Make sure every indexed solution is actually there.
+        let solution_count = (0..index_len(&puzzle.layout))
+            .filter_map(|slot| database.index_entry(slot))
+            .map(|(first, count)| first + count)
+            .max()
+            .unwrap_or(0);
+        if database.bytes.len()
+            < database.solutions_start() + solution_count * database.solution_len()
+        {
+            return Err(DatabaseError::Truncated);
+        }
+
+        // Make sure every stored placement id names one of its piece's placements.
+        for n in 0..solution_count {
+            let start = database.solutions_start() + n * database.solution_len();
+            for (i, &symbol) in database.symbols.iter().enumerate() {
+                let id = database.read_id(start + i * database.id_bytes);
+                if id >= database.placements[i].len() {
+                    return Err(DatabaseError::UnknownPlacement(symbol));
+                }
+            }
+        }
+
+        Ok(database)
+    }
+
+    /// Gets the number of solutions for a day, or `None` if the day is not in the database.
+    pub fn count_for(&self, day: &Day) -> Option<usize> {
+        self.index_entry(slot_of(&self.puzzle.layout, day)?)
+            .map(|(_, count)| count)
+    }
+
+    /// Gets the solutions for a day, or `None` if the day is not in the database.
+    pub fn solutions_for(&self, day: &Day) -> Option<Vec<Board>> {
+        let (first, count) = self.index_entry(slot_of(&self.puzzle.layout, day)?)?;
+        let board = Board::make(&self.puzzle.layout, day).ok()?;
+
+        let solutions = (first..first + count)
+            .map(|n| {
+                let start = self.solutions_start() + n * self.solution_len();
+                let mut solution = board.clone();
+                for (i, &symbol) in self.symbols.iter().enumerate() {
+                    let id = self.read_id(start + i * self.id_bytes);
+                    solution.fill_mask(self.placements[i][id], symbol);
+                }
+                solution
+            })
+            .collect();
+
+        Some(solutions)
+    }
+
+    /// Builds a database from the solutions of each date of a puzzle.
+    pub fn build<'a, I>(puzzle: &Puzzle, dates: I) -> Result<Vec<u8>, DatabaseError>
+    where
+        I: IntoIterator<Item = (&'a Day, Vec<String>)>,
+    {
+        let (width, height) = (puzzle.layout.width, puzzle.layout.height);
+        let placements = placement_table(puzzle);
+        let id_bytes = match placements.iter().map(|(_, masks)| masks.len()).max() {
+            Some(most) if most > u8::MAX as usize + 1 => 2,
+            _ => 1,
+        };
+
+        let mut index = vec![(ABSENT, 0); index_len(&puzzle.layout)];
+        let mut solutions = Vec::new();
+        let mut solution_count = 0;
+
+        for (day, serialized) in dates {
+            let slot = slot_of(&puzzle.layout, day).ok_or_else(|| {
+                DatabaseError::InvalidSolution(format!("{} is not on the board", day))
+            })?;
+            index[slot] = (solution_count as u32, serialized.len() as u32);
+
+            for s in serialized {
+                if s.chars().count() != width * height {
+                    return Err(DatabaseError::InvalidSolution(s));
+                }
+
+                for (symbol, masks) in &placements {
+                    let mask = s
+                        .chars()
+                        .enumerate()
+                        .filter(|&(_, c)| c == *symbol)
+                        .fold(0u64, |mask, (i, _)| mask | (1 << i));
+                    let id = masks
+                        .binary_search(&mask)
+                        .map_err(|_| DatabaseError::UnknownPlacement(*symbol))?;
+                    solutions.extend_from_slice(&(id as u16).to_le_bytes()[..id_bytes]);
+                }
+                solution_count += 1;
+            }
+        }
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&puzzle.fingerprint().to_le_bytes());
+        bytes.extend_from_slice(&[
+            width as u8,
+            height as u8,
+            placements.len() as u8,
+            id_bytes as u8,
+        ]);
+        bytes.extend(placements.iter().map(|(symbol, _)| *symbol as u8));
+        for (first, count) in index {
+            bytes.extend_from_slice(&first.to_le_bytes());
+            bytes.extend_from_slice(&count.to_le_bytes());
+        }
+        bytes.extend(solutions);
+
+        Ok(bytes)
+    }
+
+    /// Converts a tree of `<Month>/<day>/<Weekday>.txt` solution files into a database.
+    /// Dates without a file are left out of the database.
+    pub fn import(root: &Path, puzzle: &Puzzle) -> Result<Vec<u8>, DatabaseError> {
+        let days = all_days(&puzzle.layout);
+        let mut dates = Vec::new();
+        for day in &days {
+            match fs::read_to_string(solution_path(root, day)) {
+                Ok(contents) => {
+                    let mut solutions: Vec<String> = contents.lines().map(String::from).collect();
+                    solutions.sort();
+                    dates.push((day, solutions));
+                }
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Self::build(puzzle, dates)
+    }
+
+    fn solution_len(&self) -> usize {
+        self.symbols.len() * self.id_bytes
+    }
+
+    fn solutions_start(&self) -> usize {
+        HEADER_LEN + self.symbols.len() + 8 * index_len(&self.puzzle.layout)
+    }
+
+    /// Reads the index entry at a slot, as (first solution, solution count).
+    fn index_entry(&self, slot: usize) -> Option<(usize, usize)> {
+        let start = HEADER_LEN + self.symbols.len() + 8 * slot;
+        let first = u32::from_le_bytes(self.bytes.get(start..start + 4)?.try_into().unwrap());
+        let count = u32::from_le_bytes(self.bytes.get(start + 4..start + 8)?.try_into().unwrap());
+        match first {
+            ABSENT => None,
+            _ => Some((first as usize, count as usize)),
+        }
+    }
+
+    fn read_id(&self, at: usize) -> usize {
+        match self.id_bytes {
+            1 => self.bytes[at] as usize,
+            _ => u16::from_le_bytes([self.bytes[at], self.bytes[at + 1]]) as usize,
+        }
+    }
+}
+
+/// Gets the number of index slots for a layout.
+fn index_len(layout: &Layout) -> usize {
+    YEAR_INDEX_LEN * layout.years().len().max(1)
+}
+
+/// Gets the index slot of a day on a layout, or `None` if the day's year is not on the board.
+fn slot_of(layout: &Layout, day: &Day) -> Option<usize> {
+    let year = match day.year {
+        Some(year) => layout.years().iter().position(|&y| y == year)?,
+        None => 0,
+    };
+    let month = day.month.clone() as usize;
+    let month_day = day.day.value() as usize - 1;
+    let weekday = day.weekday.clone().map_or(7, |w| w as usize);
+    Some(year * YEAR_INDEX_LEN + (month * 31 + month_day) * 8 + weekday)
+}
+
+/// Gets each of a puzzle's pieces' symbol and its placement masks on an empty board,
+/// which are already sorted and distinct.
+fn placement_table(puzzle: &Puzzle) -> Vec<(char, Vec<u64>)> {
+    let board = Board::new(puzzle.layout.width, puzzle.layout.height, '·');
+    puzzle
+        .pieces_for_board(&board)
+        .iter()
+        .map(|piece| (piece.symbol, piece.get_allowed_masks().to_vec()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calendar::{Month, MonthDay, Weekday};
+    use crate::fixtures::march_3_tuesday;
+
+    #[test]
+    fn test_round_trip() {
+        let known = march_3_tuesday();
+        let (day, solutions) = (&known.day, known.solutions);
+
+        let puzzle = Puzzle::calendarium();
+        let bytes = SolutionDatabase::build(&puzzle, [(day, solutions.clone())]).unwrap();
+        let database = SolutionDatabase::from_bytes(bytes, &puzzle).unwrap();
+
+        let boards = database.solutions_for(day).unwrap();
+        let serialized: Vec<String> = boards.iter().map(|b| b.serialize()).collect();
+        assert_eq!(serialized, solutions);
+
+        let other = Day::new(
+            Month::March,
+            MonthDay::new(3).unwrap(),
+            Some(Weekday::Monday),
+        )
+        .unwrap();
+        assert!(database.solutions_for(&other).is_none());
+    }
+
+    #[test]
+    fn test_rejects_unknown_placement_ids() {
+        let known = march_3_tuesday();
+        let puzzle = Puzzle::calendarium();
+        let mut bytes = SolutionDatabase::build(&puzzle, [(&known.day, known.solutions)]).unwrap();
+
+        // Overwrite the last placement id with one that no piece has.
+        *bytes.last_mut().unwrap() = u8::MAX;
+        let last_symbol = puzzle.pieces().last().unwrap().symbol;
+        assert!(matches!(
+            SolutionDatabase::from_bytes(bytes, &puzzle),
+            Err(DatabaseError::UnknownPlacement(symbol)) if symbol == last_symbol
+        ));
+    }
+}
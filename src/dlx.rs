@@ -0,0 +1,223 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// An exact cover matrix, solved with Donald Knuth's "Dancing Links" (Algorithm X).
+///
+/// Every row covers a set of columns, and a solution is a set of rows that
+/// together cover every column exactly once. The matrix is stored as a toroidal
+/// doubly-linked list of nodes, laid out in flat vectors for cache friendliness.
+pub struct Dlx {
+    left: Vec<usize>,
+    right: Vec<usize>,
+    up: Vec<usize>,
+    down: Vec<usize>,
+    column: Vec<usize>, // Column header of each node
+    row: Vec<usize>,    // Row id of each node (unused for headers)
+    size: Vec<usize>,   // Number of nodes in each column
+    column_count: usize,
+}
+
+/// The root node sits at index 0, followed by one header node per column.
+const ROOT: usize = 0;
+
+impl Dlx {
+    /// Creates an empty matrix with the given number of columns.
+    pub fn new(column_count: usize) -> Self {
+        let header_count = column_count + 1;
+        let mut dlx = Dlx {
+            left: Vec::with_capacity(header_count),
+            right: Vec::with_capacity(header_count),
+            up: Vec::with_capacity(header_count),
+            down: Vec::with_capacity(header_count),
+            column: Vec::with_capacity(header_count),
+            row: Vec::with_capacity(header_count),
+            size: vec![0; header_count],
+            column_count,
+        };
+
+        // Link the root and the column headers into a circular list.
+        for i in 0..header_count {
+            dlx.left.push(if i == 0 { column_count } else { i - 1 });
+            dlx.right.push(if i == column_count { 0 } else { i + 1 });
+            dlx.up.push(i);
+            dlx.down.push(i);
+            dlx.column.push(i);
+            dlx.row.push(usize::MAX);
+        }
+
+        dlx
+    }
+
+    /// Adds a row covering the given (zero-based) columns.
+    pub fn add_row(&mut self, row_id: usize, columns: &[usize]) {
+        let mut first: Option<usize> = None;
+
+        for &col in columns {
+            assert!(col < self.column_count, "Column {} is out of range.", col);
+            let header = col + 1;
+            let node = self.left.len();
+
+            // Insert vertically at the bottom of the column.
+            self.up.push(self.up[header]);
+            self.down.push(header);
+            self.down[self.up[header]] = node;
+            self.up[header] = node;
+            self.column.push(header);
+            self.row.push(row_id);
+            self.size[header] += 1;
+
+            // Insert horizontally at the end of the row.
+            match first {
+                Some(first) => {
+                    self.left.push(self.left[first]);
+                    self.right.push(first);
+                    self.right[self.left[first]] = node;
+                    self.left[first] = node;
+                }
+                None => {
+                    self.left.push(node);
+                    self.right.push(node);
+                    first = Some(node);
+                }
+            }
+        }
+    }
+
+    /// Finds exact covers, calling `on_solution` with the row ids of each one.
+    /// The search stops early as soon as `on_solution` returns `false`, or `stop` is set.
+    pub fn solve<F>(&mut self, stop: &AtomicBool, mut on_solution: F)
+    where
+        F: FnMut(&[usize]) -> bool,
+    {
+        let mut partial = Vec::new();
+        self.search(&mut partial, stop, &mut on_solution);
+    }
+
+    /// Recursive step of Algorithm X. Returns `false` if the search should stop.
+    fn search<F>(
+        &mut self,
+        partial: &mut Vec<usize>,
+        stop: &AtomicBool,
+        on_solution: &mut F,
+    ) -> bool
+    where
+        F: FnMut(&[usize]) -> bool,
+    {
+        if stop.load(Ordering::Relaxed) {
+            return false;
+        }
+
+        if self.right[ROOT] == ROOT {
+            return on_solution(partial);
+        }
+
+        // Choose the column with the fewest remaining rows (Knuth's "S" heuristic).
+        let mut col = self.right[ROOT];
+        let mut j = self.right[col];
+        while j != ROOT {
+            if self.size[j] < self.size[col] {
+                col = j;
+            }
+            j = self.right[j];
+        }
+
+        if self.size[col] == 0 {
+            return true; // Dead end
+        }
+
+        self.cover(col);
+
+        let mut r = self.down[col];
+        while r != col {
+            partial.push(self.row[r]);
+
+            let mut j = self.right[r];
+            while j != r {
+                self.cover(self.column[j]);
+                j = self.right[j];
+            }
+
+            let keep_going = self.search(partial, stop, on_solution);
+
+            let mut j = self.left[r];
+            while j != r {
+                self.uncover(self.column[j]);
+                j = self.left[j];
+            }
+
+            partial.pop();
+
+            if !keep_going {
+                self.uncover(col);
+                return false;
+            }
+
+            r = self.down[r];
+        }
+
+        self.uncover(col);
+        true
+    }
+
+    /// Removes a column and every row that intersects it.
+    fn cover(&mut self, col: usize) {
+        self.right[self.left[col]] = self.right[col];
+        self.left[self.right[col]] = self.left[col];
+
+        let mut i = self.down[col];
+        while i != col {
+            let mut j = self.right[i];
+            while j != i {
+                self.down[self.up[j]] = self.down[j];
+                self.up[self.down[j]] = self.up[j];
+                self.size[self.column[j]] -= 1;
+                j = self.right[j];
+            }
+            i = self.down[i];
+        }
+    }
+
+    /// Restores a column previously removed by `cover`, in reverse order.
+    fn uncover(&mut self, col: usize) {
+        let mut i = self.up[col];
+        while i != col {
+            let mut j = self.left[i];
+            while j != i {
+                self.size[self.column[j]] += 1;
+                self.down[self.up[j]] = j;
+                self.up[self.down[j]] = j;
+                j = self.left[j];
+            }
+            i = self.up[i];
+        }
+
+        self.right[self.left[col]] = col;
+        self.left[self.right[col]] = col;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_knuth_example() {
+        // The example matrix from Knuth's "Dancing Links" paper.
+        let mut dlx = Dlx::new(7);
+        dlx.add_row(0, &[2, 4, 5]);
+        dlx.add_row(1, &[0, 3, 6]);
+        dlx.add_row(2, &[1, 2, 5]);
+        dlx.add_row(3, &[0, 3]);
+        dlx.add_row(4, &[1, 6]);
+        dlx.add_row(5, &[3, 4, 6]);
+
+        let mut solutions = Vec::new();
+        dlx.solve(&AtomicBool::new(false), |rows| {
+            let mut rows = rows.to_vec();
+            rows.sort();
+            solutions.push(rows);
+            true
+        });
+
+        assert_eq!(solutions, vec![vec![0, 3, 4]]);
+    }
+}
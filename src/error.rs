@@ -0,0 +1,134 @@
+use crate::{
+    board::{ParseError, PlacementError},
+    calendar::DayError,
+    database::DatabaseError,
+    layout::LayoutError,
+    pieces::PieceSetError,
+};
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Anything that can go wrong when setting up or solving a puzzle.
+#[derive(Debug)]
+pub enum Error {
+    /// A date that does not exist, or does not fit the board
+    InvalidDate(DayError),
+    /// A layout file that could not be read or parsed
+    Layout(PathBuf, LayoutError),
+    /// A piece set file that could not be read or parsed
+    PieceSet(PathBuf, PieceSetError),
+    /// Pieces that do not cover exactly the cells the board leaves open
+    PiecesDoNotFit(PieceSetError),
+    /// A piece that could not be placed on the board
+    Placement(PlacementError),
+    /// A serialized board that could not be parsed
+    Parse(ParseError),
+    /// A solution database that could not be read or built
+    Database(DatabaseError),
+    /// A file that could not be read or written
+    Io(PathBuf, io::Error),
+    /// A date that could not be asked for, because there is no terminal to ask on
+    Prompt(io::Error),
+    /// A hint for too few or too many pieces, with the most pieces a hint can show
+    InvalidHint(usize, usize),
+    /// Search statistics asked for from a solver that does not keep them
+    StatsNeedBacktrack,
+    /// Solution files that are not valid, unique solutions for their dates
+    InvalidSolutions(usize),
+    /// Dates without a solutions file
+    MissingSolutions(usize),
+    /// A day the pieces cannot be placed for
+    NoSolution,
+    /// A search that was stopped after the given time, before it finished
+    TimedOut(Duration),
+    /// Dates on which the search strategies found different solutions
+    StrategiesDisagree(usize),
+    /// A search that panicked, so the solutions it gave may be incomplete
+    SearchPanicked,
+}
+
+/// A `Result` with the crate's `Error`.
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::InvalidDate(e) => Some(e),
+            Error::Layout(_, e) => Some(e),
+            Error::PieceSet(_, e) | Error::PiecesDoNotFit(e) => Some(e),
+            Error::Placement(e) => Some(e),
+            Error::Parse(e) => Some(e),
+            Error::Database(e) => Some(e),
+            Error::Io(_, e) | Error::Prompt(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::InvalidDate(e) => write!(f, "Invalid date: {}", e),
+            Error::Layout(path, e) => write!(f, "Failed to load layout {}: {}", path.display(), e),
+            Error::PieceSet(path, e) => {
+                write!(f, "Failed to load pieces {}: {}", path.display(), e)
+            }
+            Error::PiecesDoNotFit(e) => write!(f, "The pieces do not fit the board: {}", e),
+            Error::Placement(e) => write!(f, "Failed to place piece: {}", e),
+            Error::Parse(e) => write!(f, "Invalid board: {}", e),
+            Error::Database(e) => write!(f, "Solution database error: {}", e),
+            Error::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            Error::Prompt(e) => write!(
+                f,
+                "Could not ask for a date ({}). Give one on the command line, like `March 3`.",
+                e
+            ),
+            Error::InvalidHint(_, max) => write!(f, "Hint number must be between 1 and {}.", max),
+            Error::StatsNeedBacktrack => {
+                write!(f, "--stats only works with the backtrack solver.")
+            }
+            Error::InvalidSolutions(files) => write!(f, "{} solution files have errors", files),
+            Error::MissingSolutions(dates) => write!(f, "{} dates have no solutions file", dates),
+            Error::NoSolution => write!(f, "There is no solution for that day."),
+            Error::TimedOut(timeout) => write!(
+                f,
+                "Incomplete: the search timed out after {:?}, so there may be more solutions.",
+                timeout
+            ),
+            Error::StrategiesDisagree(dates) => write!(
+                f,
+                "The strategies found different solutions on {} dates",
+                dates
+            ),
+            Error::SearchPanicked => write!(
+                f,
+                "The search stopped unexpectedly, so there may be more solutions."
+            ),
+        }
+    }
+}
+
+impl From<DayError> for Error {
+    fn from(e: DayError) -> Self {
+        Error::InvalidDate(e)
+    }
+}
+
+impl From<PlacementError> for Error {
+    fn from(e: PlacementError) -> Self {
+        Error::Placement(e)
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(e: ParseError) -> Self {
+        Error::Parse(e)
+    }
+}
+
+impl From<DatabaseError> for Error {
+    fn from(e: DatabaseError) -> Self {
+        Error::Database(e)
+    }
+}
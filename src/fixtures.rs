@@ -0,0 +1,40 @@
+use crate::{
+    board::Board,
+    calendar::{Day, Month, MonthDay, Weekday},
+    layout::Layout,
+    piece::Piece,
+    pieces::Pieces,
+};
+
+/// A Calendarium date with its board, the pieces to place on it, and every solution.
+pub struct KnownDate {
+    pub day: Day,
+    pub board: Board,
+    pub pieces: [Piece; 10],
+    pub solutions: Vec<String>, // Serialized, in `serialize()` order
+}
+
+/// Tuesday, March 3, with the solutions from `solutions/March/3/Tuesday.txt`.
+pub fn march_3_tuesday() -> KnownDate {
+    let day = Day::new(
+        Month::March,
+        MonthDay::new(3).unwrap(),
+        Some(Weekday::Tuesday),
+    )
+    .unwrap();
+    let board = Board::make(&Layout::calendarium(), &day).unwrap();
+    let pieces = Pieces::get_defaults_for_board(&board);
+
+    let mut solutions: Vec<String> = include_str!("../solutions/March/3/Tuesday.txt")
+        .lines()
+        .map(String::from)
+        .collect();
+    solutions.sort();
+
+    KnownDate {
+        day,
+        board,
+        pieces,
+        solutions,
+    }
+}
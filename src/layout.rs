@@ -0,0 +1,382 @@
+use crate::{
+    board::MAX_CELLS,
+    calendar::{Month, MonthDay, Weekday},
+    piece::Coordinates,
+};
+use std::fs;
+use std::io;
+use std::path::Path;
+use strum::IntoEnumIterator;
+
+/// The built-in layout of the Calendarium board.
+const CALENDARIUM: &str = include_str!("../layouts/calendarium.txt");
+
+/// The built-in layout of the A-Puzzle-A-Day board.
+const A_PUZZLE_A_DAY: &str = include_str!("../layouts/a-puzzle-a-day.txt");
+
+/// A layout file that could not be read or parsed.
+#[derive(Debug)]
+pub enum LayoutError {
+    /// The file could not be read
+    Io(io::Error),
+    /// The file has no rows
+    Empty,
+    /// A row with a different number of cells than the first
+    RaggedRow {
+        /// The line of the file, from 1
+        line: usize,
+        /// The cells in the first row
+        expected: usize,
+        /// The cells in this row
+        found: usize,
+    },
+    /// More cells than a board can have
+    TooManyCells(usize),
+    /// A token that is not a label
+    UnknownLabel {
+        /// The line of the file, from 1
+        line: usize,
+        /// The token
+        label: String,
+    },
+    /// A label on more than one cell
+    DuplicateLabel(String),
+    /// A label the layout needs, since it has others of its kind
+    MissingLabel(String),
+}
+
+impl std::error::Error for LayoutError {}
+
+impl std::fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LayoutError::Io(e) => write!(f, "{}", e),
+            LayoutError::Empty => write!(f, "The layout has no rows"),
+            LayoutError::RaggedRow {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Line {}: expected {} cells, found {}",
+                line, expected, found
+            ),
+            LayoutError::TooManyCells(cells) => write!(
+                f,
+                "The layout has {} cells, but at most {} are supported",
+                cells, MAX_CELLS
+            ),
+            LayoutError::UnknownLabel { line, label } => {
+                write!(f, "Line {}: unknown label \"{}\"", line, label)
+            }
+            LayoutError::DuplicateLabel(label) => write!(f, "\"{}\" appears more than once", label),
+            LayoutError::MissingLabel(label) => write!(f, "\"{}\" is missing", label),
+        }
+    }
+}
+
+impl From<io::Error> for LayoutError {
+    fn from(e: io::Error) -> Self {
+        LayoutError::Io(e)
+    }
+}
+
+/// The shape of a calendar board, and which label is printed in which cell.
+///
+/// Layouts are written as a plain text grid, one row per line and one whitespace-separated
+/// token per cell: a month (`Jan` or `January`), a day of the month (`1` to `31`),
+/// a weekday (`Mon` or `Monday`), a four-digit year (`2025`), `.` for an unlabelled cell,
+/// or `#` for a cell that is not part of the board. Blank lines and lines starting with `//`
+/// are ignored.
+///
+/// A layout may leave out a whole kind of label (such as weekdays), but if it has any
+/// label of a kind, it must have all of them. Years are the exception: a board can have
+/// cells for as many or as few years as it likes.
+#[derive(Debug, Clone)]
+pub struct Layout {
+    /// The number of columns
+    pub width: usize,
+    /// The number of rows
+    pub height: usize,
+    blocked: Vec<Coordinates>,
+    months: Vec<Option<Coordinates>>,   // Indexed by month
+    days: Vec<Option<Coordinates>>,     // Indexed by day of the month, from 0
+    weekdays: Vec<Option<Coordinates>>, // Indexed by weekday
+    years: Vec<(i32, Coordinates)>,     // In the order they appear
+}
+
+impl Layout {
+    /// Gets the built-in layout of the Calendarium board.
+    pub fn calendarium() -> Self {
+        Self::parse(CALENDARIUM).expect("The Calendarium layout is valid.")
+    }
+
+    /// Gets the built-in layout of the A-Puzzle-A-Day board, which has no weekdays.
+    pub fn a_puzzle_a_day() -> Self {
+        Self::parse(A_PUZZLE_A_DAY).expect("The A-Puzzle-A-Day layout is valid.")
+    }
+
+    /// Loads a layout from a file.
+    pub fn load(path: &Path) -> Result<Self, LayoutError> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Parses a layout from its text grid.
+    pub fn parse(text: &str) -> Result<Self, LayoutError> {
+        let mut layout = Layout {
+            width: 0,
+            height: 0,
+            blocked: Vec::new(),
+            months: vec![None; Month::iter().count()],
+            days: vec![None; 31],
+            weekdays: vec![None; Weekday::iter().count()],
+            years: Vec::new(),
+        };
+
+        for (i, line) in text.lines().enumerate() {
+            let line_number = i + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with("//") {
+                continue;
+            }
+
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            if layout.height == 0 {
+                layout.width = tokens.len();
+            } else if tokens.len() != layout.width {
+                return Err(LayoutError::RaggedRow {
+                    line: line_number,
+                    expected: layout.width,
+                    found: tokens.len(),
+                });
+            }
+
+            let y = layout.height as i32;
+            for (x, token) in tokens.into_iter().enumerate() {
+                layout.add_cell(token, (x as i32, y), line_number)?;
+            }
+            layout.height += 1;
+        }
+
+        if layout.height == 0 {
+            return Err(LayoutError::Empty);
+        }
+        if layout.width * layout.height > MAX_CELLS {
+            return Err(LayoutError::TooManyCells(layout.width * layout.height));
+        }
+
+        layout.check_complete()?;
+        Ok(layout)
+    }
+
+    /// Records what a single token says about the cell at the given coordinates.
+    fn add_cell(
+        &mut self,
+        token: &str,
+        coordinates: Coordinates,
+        line: usize,
+    ) -> Result<(), LayoutError> {
+        let slot = match token {
+            "." => return Ok(()),
+            "#" => {
+                self.blocked.push(coordinates);
+                return Ok(());
+            }
+            _ => {
+                if let Some(month) = Month::iter().find(|m| label_matches(token, m.as_ref())) {
+                    &mut self.months[month as usize]
+                } else if let Some(weekday) =
+                    Weekday::iter().find(|w| label_matches(token, w.as_ref()))
+                {
+                    &mut self.weekdays[weekday as usize]
+                } else if let Some(day) = token.parse::<u8>().ok().and_then(MonthDay::new) {
+                    &mut self.days[day.value() as usize - 1]
+                } else if let Some(year) = token.parse::<i32>().ok().filter(|_| token.len() == 4) {
+                    if self.year_cell(year).is_some() {
+                        return Err(LayoutError::DuplicateLabel(token.to_string()));
+                    }
+                    self.years.push((year, coordinates));
+                    return Ok(());
+                } else {
+                    return Err(LayoutError::UnknownLabel {
+                        line,
+                        label: token.to_string(),
+                    });
+                }
+            }
+        };
+
+        match slot {
+            Some(_) => Err(LayoutError::DuplicateLabel(token.to_string())),
+            None => {
+                *slot = Some(coordinates);
+                Ok(())
+            }
+        }
+    }
+
+    /// Makes sure each kind of label is either entirely present or entirely absent.
+    fn check_complete(&self) -> Result<(), LayoutError> {
+        fn check(cells: &[Option<Coordinates>], names: Vec<String>) -> Result<(), LayoutError> {
+            if cells.iter().all(|c| c.is_none()) {
+                return Ok(());
+            }
+            match cells.iter().zip(names).find(|(c, _)| c.is_none()) {
+                Some((_, name)) => Err(LayoutError::MissingLabel(name)),
+                None => Ok(()),
+            }
+        }
+
+        check(&self.months, Month::iter().map(|m| m.to_string()).collect())?;
+        check(&self.days, (1..=31).map(|d| d.to_string()).collect())?;
+        check(
+            &self.weekdays,
+            Weekday::iter().map(|w| w.to_string()).collect(),
+        )
+    }
+
+    /// Gets the cells that are not part of the board.
+    pub fn blocked(&self) -> &[Coordinates] {
+        &self.blocked
+    }
+
+    /// Gets the cell labelled with a month, if the layout has months.
+    pub fn month_cell(&self, month: &Month) -> Option<Coordinates> {
+        self.months[month.clone() as usize]
+    }
+
+    /// Gets the cell labelled with a day of the month, if the layout has days.
+    pub fn day_cell(&self, day: &MonthDay) -> Option<Coordinates> {
+        self.days[day.value() as usize - 1]
+    }
+
+    /// Gets the cell labelled with a weekday, if the layout has weekdays.
+    pub fn weekday_cell(&self, weekday: &Weekday) -> Option<Coordinates> {
+        self.weekdays[weekday.clone() as usize]
+    }
+
+    /// Gets the cell labelled with a year, if the layout has that year.
+    pub fn year_cell(&self, year: i32) -> Option<Coordinates> {
+        self.years
+            .iter()
+            .find(|(y, _)| *y == year)
+            .map(|&(_, cell)| cell)
+    }
+
+    /// Gets the years the layout has cells for, in the order they appear.
+    pub fn years(&self) -> Vec<i32> {
+        self.years.iter().map(|&(year, _)| year).collect()
+    }
+
+    /// Checks whether the layout has any year cells.
+    pub fn has_years(&self) -> bool {
+        !self.years.is_empty()
+    }
+
+    /// Gets the number of cells left for the pieces to cover on any day: every cell on the
+    /// board, except one for each kind of label the layout has.
+    pub fn open_cells(&self) -> usize {
+        let label_kinds = [
+            self.months.iter().any(|c| c.is_some()),
+            self.days.iter().any(|c| c.is_some()),
+            self.has_weekdays(),
+            self.has_years(),
+        ];
+        let labels = label_kinds.iter().filter(|&&has| has).count();
+        self.width * self.height - self.blocked.len() - labels
+    }
+
+    /// Checks whether the layout has a cell for each weekday.
+    pub fn has_weekdays(&self) -> bool {
+        self.weekdays.iter().all(|cell| cell.is_some())
+    }
+
+    /// Gets a short, stable hash of the layout, to tell layouts apart in saved solutions.
+    pub fn fingerprint(&self) -> u64 {
+        // The board shape and the coordinates of every cell, in a fixed order.
+        let cells = [self.width as i32, self.height as i32, -1]
+            .into_iter()
+            .chain(self.blocked.iter().flat_map(|&(x, y)| [x, y]))
+            .chain([-1])
+            .chain(
+                [&self.months, &self.days, &self.weekdays]
+                    .into_iter()
+                    .flatten()
+                    .flat_map(|cell| cell.map_or([-1, -1], |(x, y)| [x, y])),
+            )
+            .chain(self.years.iter().flat_map(|&(year, (x, y))| [year, x, y]));
+
+        fnv1a(cells.flat_map(i32::to_le_bytes))
+    }
+}
+
+/// Hashes bytes with FNV-1a, which is simple and the same on every platform and Rust version,
+/// so fingerprints can be saved and compared later.
+pub(crate) fn fnv1a(bytes: impl IntoIterator<Item = u8>) -> u64 {
+    bytes.into_iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Checks whether a token names a label, by its full name or its first three letters.
+fn label_matches(token: &str, name: &str) -> bool {
+    token.eq_ignore_ascii_case(name) || token.eq_ignore_ascii_case(&name[..3])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_calendarium() {
+        let layout = Layout::calendarium();
+        assert_eq!((layout.width, layout.height), (9, 6));
+        assert_eq!(layout.blocked(), &[(8, 5)]);
+        assert_eq!(layout.month_cell(&Month::October), Some((1, 5)));
+        assert_eq!(layout.day_cell(&MonthDay::new(31).unwrap()), Some((5, 2)));
+        assert_eq!(layout.weekday_cell(&Weekday::Sunday), Some((8, 4)));
+        assert!(layout.has_weekdays());
+    }
+
+    #[test]
+    fn test_a_puzzle_a_day() {
+        let layout = Layout::a_puzzle_a_day();
+        assert_eq!((layout.width, layout.height), (7, 7));
+        assert_eq!(layout.blocked().len(), 6);
+        assert_eq!(layout.day_cell(&MonthDay::new(31).unwrap()), Some((2, 6)));
+        assert!(!layout.has_weekdays());
+        assert!(!layout.has_years());
+    }
+
+    #[test]
+    fn test_years() {
+        let layout = Layout::parse("2025 .\n. 2026").unwrap();
+        assert_eq!(layout.years(), vec![2025, 2026]);
+        assert_eq!(layout.year_cell(2026), Some((1, 1)));
+        assert_eq!(layout.year_cell(2027), None);
+        assert!(matches!(
+            Layout::parse("2025 2025"),
+            Err(LayoutError::DuplicateLabel(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(matches!(
+            Layout::parse("Jan Feb\n1"),
+            Err(LayoutError::RaggedRow { line: 2, .. })
+        ));
+        assert!(matches!(
+            Layout::parse("Jan Foo"),
+            Err(LayoutError::UnknownLabel { line: 1, .. })
+        ));
+        assert!(matches!(
+            Layout::parse("Mon Monday"),
+            Err(LayoutError::DuplicateLabel(_))
+        ));
+        assert!(matches!(
+            Layout::parse("Mon Tue"),
+            Err(LayoutError::MissingLabel(_))
+        ));
+    }
+}
@@ -0,0 +1,86 @@
+//! Solves calendar puzzles like Calendarium and DragonFjord's A-Puzzle-A-Day: a board of month,
+//! day and weekday cells, on which a set of pieces has to cover every cell except today's.
+//!
+//! A [`Puzzle`] is a board [`Layout`] and the [`Piece`]s placed on it. For a [`Day`], it builds
+//! the target [`Board`] with that day's cells covered, and finds the ways to fill the rest:
+//!
+//! ```
+//! use calendar_puzzle::{Board, Day, Month, MonthDay, Puzzle};
+//!
+//! // Build the target: the Calendarium board for Saturday, October 17, 2026.
+//! let puzzle = Puzzle::calendarium();
+//! let day = Day::in_year(2026, Month::October, MonthDay::new(17).unwrap())?;
+//! let target = puzzle.board_for(&day)?;
+//!
+//! // List the solutions, which are streamed as they are found.
+//! let solutions: Vec<Board> = puzzle.solutions(&day)?.collect();
+//! assert!(!solutions.is_empty());
+//!
+//! // Give a hint: the first solution with only three of its pieces.
+//! let hint = solutions[0].hint_pieces(3);
+//! assert_ne!(hint, solutions[0]);
+//!
+//! // Serialize a solution to one line of text, and read it back.
+//! let text = solutions[0].serialize();
+//! let parsed = Board::deserialize(target.width, target.height, &text, puzzle.pieces())?;
+//! assert_eq!(parsed, solutions[0]);
+//! # Ok::<(), calendar_puzzle::Error>(())
+//! ```
+//!
+//! [`Puzzle::solve`] and [`Puzzle::count`] take [`SolveOptions`] to choose the [`Solver`], only
+//! give the first solution, keep to `serialize()` order or stop after a timeout, and answer from
+//! a [`SolutionCache`] of precomputed solutions when it has the day.
+//!
+//! For more control, [`Board`] has the searches themselves: backtracking in either [`Strategy`],
+//! Dancing Links, counting, and finding the lexicographically smallest solution. Searches can be
+//! watched through [`SearchStats`] and stopped with a [`CancellationToken`]. Anything that goes
+//! wrong is an [`Error`].
+
+#![warn(missing_docs)]
+
+/// Timing the two backtracking strategies against each other over many dates.
+pub mod benchmark;
+/// The board, and the searches that place pieces on it.
+pub mod board;
+/// Precomputed solutions, from a database or a tree of solution files.
+pub mod cache;
+/// Dates, and the parts of them that a board shows.
+pub mod calendar;
+/// A compact binary database of precomputed solutions.
+pub mod database;
+/// Knuth's Dancing Links, for exact cover problems.
+pub mod dlx;
+/// The errors that setting up or solving a puzzle can give.
+pub mod error;
+#[cfg(test)]
+mod fixtures;
+/// Board layouts: which cell shows which month, day, weekday or year.
+pub mod layout;
+/// Pieces, their shapes, and where they can be placed.
+pub mod piece;
+/// The built-in piece sets, and piece set files.
+pub mod pieces;
+/// Puzzles, made of a layout and its pieces, and solving them for a day.
+pub mod puzzle;
+/// Solving every date of a puzzle, and where the solution files go.
+pub mod solve_all;
+/// The choice of solver, and the options for a search.
+pub mod solver;
+/// Counting what a search does, and reporting its progress.
+pub mod stats;
+/// Streaming solutions from a search on a background thread, and cancelling it.
+pub mod stream;
+/// Checking solution files against a puzzle.
+pub mod verify;
+
+pub use board::Board;
+pub use cache::SolutionCache;
+pub use calendar::{Day, DayError, Month, MonthDay, Weekday};
+pub use error::{Error, Result};
+pub use layout::Layout;
+pub use piece::Piece;
+pub use pieces::Pieces;
+pub use puzzle::{Puzzle, Solutions};
+pub use solver::{SolveOptions, Solver, Strategy};
+pub use stats::SearchStats;
+pub use stream::{CancellationToken, SolutionStream};
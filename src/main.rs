@@ -1,74 +1,246 @@
-mod board;
-mod calendar;
 mod cli;
-mod piece;
-mod pieces;
 
-use board::Board;
+use calendar_puzzle::{
+    benchmark,
+    cache::SolutionCache,
+    database::SolutionDatabase,
+    error::{Error, Result},
+    layout::Layout,
+    pieces::Pieces,
+    puzzle::Puzzle,
+    solve_all,
+    solver::{SolveOptions, Solver},
+    stats::{ProgressReporter, SearchStats},
+    verify,
+};
 use clap::Parser;
-use cli::{show_pieces, Args};
-use piece::Piece;
-use pieces::Pieces;
-
-use std::sync::atomic::AtomicBool;
+use cli::{exit_code, print_stats, show_pieces, Args, Command, PuzzleName};
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Instant;
 
 fn main() {
-    let args = Args::parse();
+    if let Err(e) = run(Args::parse()) {
+        eprintln!("{}", e);
+        std::process::exit(exit_code(&e));
+    }
+}
+
+fn run(args: Args) -> Result<()> {
+    // Only the backtrack solver keeps statistics, so check before anything is printed
+    let solver = Solver::from(args.solver);
+    if args.stats && solver != Solver::Backtrack {
+        return Err(Error::StatsNeedBacktrack);
+    }
+
+    // Handle --puzzle flag by choosing a built-in puzzle
+    let puzzle = match args.puzzle {
+        PuzzleName::Calendarium => Puzzle::calendarium(),
+        PuzzleName::APuzzleADay => Puzzle::a_puzzle_a_day(),
+    };
+
+    // Handle --layout flag by loading a custom board layout
+    let puzzle = match &args.layout {
+        Some(path) => {
+            puzzle.with_layout(Layout::load(path).map_err(|e| Error::Layout(path.clone(), e))?)
+        }
+        None => puzzle,
+    };
 
-    let day = args.get_day();
-    let board = Board::make(&day);
+    // Handle --pieces flag by loading a custom piece set
+    let puzzle = match &args.pieces {
+        Some(path) => {
+            puzzle.with_pieces(Pieces::load(path).map_err(|e| Error::PieceSet(path.clone(), e))?)
+        }
+        None => puzzle,
+    };
+
+    // Handle --allow-flips flag by making every piece two-sided
+    let puzzle = match args.allow_flips {
+        true => puzzle.with_flips(),
+        false => puzzle,
+    };
+
+    puzzle.check_area()?;
 
-    // Handle --hint flag by only displaying the first solution with a certain number
+    // Solution files go in the puzzle's own directory, unless another one is given
+    let solutions_dir = |dir: &Option<PathBuf>| dir.clone().unwrap_or(args.puzzle.solutions_dir());
+
+    match &args.command {
+        Some(Command::SolveAll { output }) => {
+            let output = solutions_dir(output);
+            let summary = solve_all::solve_all(
+                &output,
+                &puzzle,
+                args.solver.into(),
+                args.strategy.into(),
+                |day| eprintln!("Solved {}", day),
+            )?;
+            eprintln!(
+                "Solved {} dates ({} already solved).",
+                summary.solved, summary.skipped
+            );
+            return Ok(());
+        }
+        Some(Command::ImportSolutions { from, to }) => {
+            let bytes = SolutionDatabase::import(&solutions_dir(from), &puzzle)?;
+            std::fs::write(to, &bytes).map_err(|e| Error::Io(to.clone(), e))?;
+            eprintln!("Wrote {} bytes to {}.", bytes.len(), to.display());
+            return Ok(());
+        }
+        Some(Command::Verify { from }) => {
+            let from = solutions_dir(from);
+            let report =
+                verify::verify_tree(&from, &puzzle).map_err(|e| Error::Io(from.clone(), e))?;
+            for (path, errors) in &report.errors {
+                for error in errors {
+                    println!("{}:{}: {}", path.display(), error.line, error.message);
+                }
+            }
+            for path in &report.missing {
+                eprintln!("Missing {}", path.display());
+            }
+            eprintln!(
+                "Checked {} files: {} with errors, {} missing.",
+                report.checked,
+                report.errors.len(),
+                report.missing.len()
+            );
+            if !report.errors.is_empty() {
+                return Err(Error::InvalidSolutions(report.errors.len()));
+            }
+            return match report.missing.len() {
+                0 => Ok(()),
+                dates => Err(Error::MissingSolutions(dates)),
+            };
+        }
+        Some(Command::Benchmark { every }) => {
+            let report = benchmark::benchmark(&puzzle, *every, |day, piece_first, cell_first| {
+                eprintln!(
+                    "{}: piece-first {:.3}s, cell-first {:.3}s",
+                    day,
+                    piece_first.as_secs_f64(),
+                    cell_first.as_secs_f64()
+                )
+            })?;
+            for (name, timing) in [
+                ("piece-first", &report.piece_first),
+                ("cell-first", &report.cell_first),
+            ] {
+                println!(
+                    "{}: {:.1}s in total, {:.3}s per date, faster on {} dates, slowest {:.3}s on {}",
+                    name,
+                    timing.total.as_secs_f64(),
+                    timing.total.as_secs_f64() / report.dates.max(1) as f64,
+                    timing.faster_on,
+                    timing.slowest.as_secs_f64(),
+                    timing.slowest_day.as_deref().unwrap_or("-")
+                );
+                let stats = &timing.stats;
+                println!(
+                    "  {} placements tried, {} pruned: {} by size, {} by shape, {} by pair",
+                    stats.placements.load(Ordering::Relaxed),
+                    stats.pruned(),
+                    stats.pruned_by_size.load(Ordering::Relaxed),
+                    stats.pruned_by_shape.load(Ordering::Relaxed),
+                    stats.pruned_by_pair.load(Ordering::Relaxed)
+                );
+            }
+            for day in &report.disagreements {
+                println!("The strategies found different solutions for {}", day);
+            }
+            return match report.disagreements.len() {
+                0 => Ok(()),
+                dates => Err(Error::StrategiesDisagree(dates)),
+            };
+        }
+        None => {}
+    }
+
+    let day = args.get_day(&puzzle.layout)?;
+    let board = puzzle.board_for(&day)?;
+
+    // Handle --hint flag by only displaying a certain number of pieces, leaving at least one out
+    let pieces_in_hint = 1..puzzle.pieces().len();
     let hint = match args.hint {
-        Some(hint) if hint < 10 && hint > 0 => Some(hint),
-        Some(_hint) => {
-            eprintln!("Hint number must be between 1 and 9.");
-            std::process::exit(1);
+        Some(hint) if pieces_in_hint.contains(&hint) => Some(hint),
+        Some(hint) => {
+            return Err(Error::InvalidHint(
+                hint,
+                pieces_in_hint.end.saturating_sub(1),
+            ))
         }
         None => None,
     };
 
     if !args.raw {
-        println!("{}, {} {}", day.weekday, day.month, day.day);
-        board.display();
+        println!("{}", day);
+        board.display(puzzle.pieces());
         println!();
     }
 
     // Define the pieces to place
-    let default_pieces = Pieces::get_defaults_for_board(&board);
+    let default_pieces = puzzle.pieces_for_board(&board);
 
     // Handle --show-pieces flag
     if !args.raw && args.show_pieces {
         show_pieces(&default_pieces);
     }
 
-    // Create a mutable list of references to the default pieces.
-    let mut pieces: Vec<&Piece> = default_pieces.iter().collect();
+    // Look for precomputed solutions, unless --no-cache asks for a fresh solve.
+    let cache = match args.no_cache {
+        true => None,
+        false => SolutionCache::find(args.database.as_deref(), &puzzle)?,
+    };
 
-    // Generate all valid boards that place all pieces.
-    let mut final_boards = board
-        .find_boards_placing_all_pieces(
-            &mut pieces,
-            &AtomicBool::new(false), // Whether any solutions have been found
-            args.all,                // Whether to find all solutions
-        )
-        .into_iter()
-        .collect::<Vec<Board>>();
+    let options = SolveOptions {
+        solver,
+        strategy: args.strategy.into(),
+        all: args.all,
+        deterministic: args.deterministic,
+        timeout: args.timeout,
+        stats: Arc::new(SearchStats::default()),
+    };
 
-    final_boards.sort_by(|a, b| a.serialize().cmp(&b.serialize()));
+    // Handle --count flag by counting solutions without building them.
+    if args.count {
+        let count = puzzle.count(&day, &options, args.breakdown, cache.as_ref())?;
+        match args.raw {
+            true => println!("{}", count.total),
+            false => println!("{} solutions", count.total),
+        }
 
-    // Handle --hint flag by only displaying the first solution with a certain number
-    match hint {
-        Some(hint) => {
-            final_boards = final_boards
-                .into_iter()
-                .map(|b| b.hint_pieces(hint))
-                .collect();
+        for (mask, solutions) in &count.by_first_placement {
+            let cells = board
+                .coordinates_of(*mask)
+                .iter()
+                .map(|(x, y)| format!("({}, {})", x, y))
+                .collect::<Vec<_>>()
+                .join(" ");
+            println!("{} at {}: {}", default_pieces[0].symbol, cells, solutions);
         }
-        None => {}
+        return Ok(());
     }
 
-    for (i, board) in final_boards.iter().enumerate() {
+    // Handle --stats flag by watching the search while it runs
+    let start = Instant::now();
+
+    // Use precomputed solutions if there are any for this day,
+    // or stream the boards that place all pieces as they are found.
+    let mut solutions = puzzle.solve(&day, &options, cache.as_ref())?;
+    let searched = solutions.searched();
+    let progress = (args.stats && searched).then(|| ProgressReporter::spawn(options.stats.clone()));
+
+    let mut found = 0;
+    for (i, solution) in solutions.by_ref().enumerate() {
+        found += 1;
+        // Handle --hint flag by only displaying a certain number of pieces
+        let board = match hint {
+            Some(hint) => solution.hint_pieces(hint),
+            None => solution,
+        };
+
         // Only display the solution number if --all is used
         if !args.raw && args.all {
             println!();
@@ -77,7 +249,21 @@ fn main() {
 
         match args.raw {
             true => println!("{}", board.serialize()),
-            false => board.display(),
+            false => board.display(puzzle.pieces()),
         }
     }
+
+    drop(progress);
+    if args.stats {
+        match searched {
+            true => print_stats(&options.stats, start.elapsed()),
+            false => eprintln!("The solutions were precomputed, so there was no search."),
+        }
+    }
+
+    solutions.outcome()?;
+    match found {
+        0 => Err(Error::NoSolution),
+        _ => Ok(()),
+    }
 }
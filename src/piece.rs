@@ -1,30 +1,40 @@
-use strum::IntoEnumIterator;
-
 use colored::Color;
 
 use strum_macros::EnumIter;
 
 use crate::board::Board;
 
+/// How far a piece is turned clockwise from its shape as given.
 #[derive(Debug, Clone, Copy, PartialEq, EnumIter)]
 pub enum Rotation {
-    Zero,       // 0°
-    Ninety,     // 90°
-    OneEighty,  // 180°
-    TwoSeventy, // 270°
+    /// Not turned
+    Zero,
+    /// Turned 90°
+    Ninety,
+    /// Turned 180°
+    OneEighty,
+    /// Turned 270°
+    TwoSeventy,
 }
 
+/// A cell's column and row, from the top left.
 pub type Coordinates = (i32, i32);
 
+/// Where and how a piece is placed on a board.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Placement {
+    /// How far the piece is turned
     pub rotation: Rotation,
+    /// Whether the piece is flipped over
     pub flipped: bool,
+    /// The column the piece is moved by
     pub x: i32,
+    /// The row the piece is moved by
     pub y: i32,
 }
 
 impl Placement {
+    /// Creates a placement from a rotation, whether it is flipped, and where the piece is moved to.
     pub fn new(rotation: Rotation, flipped: bool, coordinates: Coordinates) -> Self {
         Placement {
             rotation,
@@ -35,20 +45,81 @@ impl Placement {
     }
 }
 
+/// The symmetries of a piece's shape: which rotations and mirror images leave it unchanged.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Symmetry {
+    /// Every rotation and mirror image is different
+    None,
+    /// Its mirror image is a rotation of it
+    Mirror,
+    /// Turning it 180° leaves it unchanged
+    HalfTurn,
+    /// Its mirror image is a rotation of it, and turning it 180° leaves it unchanged
+    MirrorAndHalfTurn,
+    /// Turning it 90° leaves it unchanged
+    QuarterTurn,
+    /// Every rotation and mirror image is the same
+    Full,
+}
+
+impl Symmetry {
+    /// Works out the symmetry of a shape from how many of its four rotations,
+    /// and of all eight rotations and mirror images, look the same as the shape itself.
+    fn of(same_rotations: usize, same_orientations: usize) -> Self {
+        match (same_rotations, same_orientations > same_rotations) {
+            (1, false) => Symmetry::None,
+            (1, true) => Symmetry::Mirror,
+            (2, false) => Symmetry::HalfTurn,
+            (2, true) => Symmetry::MirrorAndHalfTurn,
+            (_, false) => Symmetry::QuarterTurn,
+            (_, true) => Symmetry::Full,
+        }
+    }
+
+    /// Checks whether the mirror image of the shape is a different shape, so flipping matters.
+    pub fn is_chiral(&self) -> bool {
+        matches!(
+            self,
+            Symmetry::None | Symmetry::HalfTurn | Symmetry::QuarterTurn
+        )
+    }
+}
+
+impl std::fmt::Display for Symmetry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Symmetry::None => "no symmetry",
+            Symmetry::Mirror => "mirror symmetry",
+            Symmetry::HalfTurn => "half-turn symmetry",
+            Symmetry::MirrorAndHalfTurn => "mirror and half-turn symmetry",
+            Symmetry::QuarterTurn => "quarter-turn symmetry",
+            Symmetry::Full => "full symmetry",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A piece: its shape in every orientation it can be placed in, and how it is drawn.
 #[derive(Debug, Clone)]
 pub struct Piece {
+    /// The symbol drawn on the piece's cells
     pub display_symbol: char,
+    /// The symbol that stands for the piece in serialized boards
     pub symbol: char,
+    /// The color of the symbol
     pub color: Color,
+    /// The color of the piece's cells
     pub bg: Color,
+    /// Whether the piece may be flipped over
     pub flippable: bool,
-    rotations: Vec<Vec<Coordinates>>, // Precomputed rotations
-    rotations_flipped: Option<Vec<Vec<Coordinates>>>, // Precomputed flipped rotations if flippable
-    allowed_placements: Vec<Placement>, // Allowed placements for this piece
+    orientations: Vec<Vec<Coordinates>>, // Distinct orientations, each moved to the origin with sorted cells
+    orientation_of: [Option<usize>; 8], // Orientation of each rotation, then each flipped rotation if flippable
+    symmetry: Symmetry,                 // Symmetry of the shape
+    allowed_masks: Vec<u64>,            // Occupancy masks of the allowed placements for this piece
 }
 
 impl Piece {
-    /// Creates a new Piece with precomputed rotations.
+    /// Creates a new Piece with its distinct orientations precomputed.
     pub fn new(
         symbol: char,
         shape: Vec<Coordinates>,
@@ -60,34 +131,13 @@ impl Piece {
             panic!("A piece must consist of at least 1 block.");
         }
 
-        // Precompute all rotations
-        let rotations: Vec<_> = (0..4)
-            .scan(shape.clone(), |current_shape, _| {
-                let result = current_shape.clone();
-                *current_shape = current_shape
-                    .iter()
-                    .map(|(x, y)| (*y, -*x)) // Rotate 90° clockwise
-                    .collect();
-                Some(result)
-            })
-            .collect();
-
-        let rotations_flipped = match flippable {
-            true => Some(
-                rotations
-                    .iter()
-                    .map(|rotation| {
-                        rotation
-                            .iter()
-                            .map(|(x, y)| (-*x, *y)) // Flip horizontally
-                            .collect()
-                    })
-                    .collect(),
-            ),
-            false => None,
+        // Compare every rotation and mirror image with the shape to find its symmetry.
+        let transformed = transformations_of(&shape);
+        let same = |range: std::ops::Range<usize>| {
+            range.filter(|&i| transformed[i] == transformed[0]).count()
         };
 
-        Piece {
+        let mut piece = Piece {
             color: Color::TrueColor {
                 r: color.0,
                 g: color.1,
@@ -100,39 +150,65 @@ impl Piece {
             },
             display_symbol: symbol,
             symbol,
-            rotations,
-            rotations_flipped,
             flippable,
-            allowed_placements: Vec::new(),
+            orientations: vec![transformed[0].clone()],
+            orientation_of: [None; 8],
+            symmetry: Symmetry::of(same(0..4), same(0..8)),
+            allowed_masks: Vec::new(),
+        };
+        piece.set_flippable(flippable);
+        piece
+    }
+
+    /// Sets whether the piece may be flipped over, and works out its distinct orientations again.
+    pub fn set_flippable(&mut self, flippable: bool) {
+        let transformed = transformations_of(&self.orientations[0]);
+        let allowed = match flippable {
+            true => 8,
+            false => 4,
+        };
+
+        self.flippable = flippable;
+        self.orientations.clear();
+        self.orientation_of = [None; 8];
+        for (i, shape) in transformed.into_iter().take(allowed).enumerate() {
+            let index = match self.orientations.iter().position(|o| *o == shape) {
+                Some(index) => index,
+                None => {
+                    self.orientations.push(shape);
+                    self.orientations.len() - 1
+                }
+            };
+            self.orientation_of[i] = Some(index);
         }
     }
 
-    /// Get the dimensions of the default (unrotated) shape.
-    pub fn get_dimensions_of_shape(&self, rotation: Rotation, flipped: bool) -> (i32, i32) {
-        // Iterate over the shape and find the maximum x and y values of its coordinates
-        // which is therefore the width and height of the shape.
-        self.get_shape(rotation, flipped)
-            .iter()
-            .fold((0, 0), |(max_x, max_y), &(x, y)| {
-                (max_x.max(x + 1), max_y.max(y + 1))
-            })
+    /// Gets the symmetry of the piece's shape.
+    pub fn symmetry(&self) -> Symmetry {
+        self.symmetry
+    }
+
+    /// Gets the distinct orientations the piece can be placed in, starting with its own shape.
+    /// Each one is moved so its top-left corner is at the origin, with its cells in order.
+    pub fn orientations(&self) -> &[Vec<Coordinates>] {
+        &self.orientations
     }
 
     /// Get the shape at a specific rotation.
     pub fn get_shape(&self, rotation: Rotation, flipped: bool) -> &Vec<Coordinates> {
-        let index = match rotation {
-            Rotation::Zero => 0,
-            Rotation::Ninety => 1,
-            Rotation::OneEighty => 2,
-            Rotation::TwoSeventy => 3,
-        };
-
-        match flipped {
-            true => &self.rotations_flipped.as_ref().unwrap()[index],
-            false => &self.rotations[index],
+        let index = rotation as usize + if flipped { 4 } else { 0 };
+        match self.orientation_of[index] {
+            Some(orientation) => &self.orientations[orientation],
+            None => panic!("Piece {} cannot be flipped.", self.symbol),
         }
     }
 
+    /// Gets the number of cells the piece covers.
+    pub fn size(&self) -> usize {
+        self.orientations[0].len()
+    }
+
+    /// Draws the piece with another symbol than the one it is serialized with.
     pub fn display_as(&mut self, symbol: char) {
         self.display_symbol = symbol;
     }
@@ -140,47 +216,153 @@ impl Piece {
     /// Precomputes and stores allowed placements for this piece based on the given board.
     /// The board provides its dimensions and current occupied cells (treated as forbidden).
     pub fn precompute_allowed_placements(&mut self, board: &Board) {
-        let mut allowed_placements = Vec::new();
+        let mut allowed_masks = Vec::new();
 
-        for rotation in Rotation::iter() {
-            for flip in [false, true] {
-                if flip && !self.flippable {
-                    continue;
-                }
-                let shape = self.get_shape(rotation, flip);
-
-                for y in 0..board.height as i32 {
-                    for x in 0..board.width as i32 {
-                        let mut is_valid = true;
-
-                        for &(dx, dy) in shape {
-                            let xx = x + dx;
-                            let yy = y + dy;
-
-                            if xx < 0
-                                || xx >= board.width as i32
-                                || yy < 0
-                                || yy >= board.height as i32
-                                || board.grid[yy as usize][xx as usize].is_some()
-                            {
-                                is_valid = false;
-                                break;
-                            }
-                        }
+        // Only distinct orientations are tried, so every placement is found exactly once.
+        for shape in &self.orientations {
+            for y in 0..board.height as i32 {
+                for x in 0..board.width as i32 {
+                    let mut is_valid = true;
+                    let mut mask = 0;
+
+                    for &(dx, dy) in shape {
+                        let xx = x + dx;
+                        let yy = y + dy;
 
-                        if is_valid {
-                            allowed_placements.push(Placement::new(rotation, flip, (x, y)));
+                        if xx < 0
+                            || xx >= board.width as i32
+                            || yy < 0
+                            || yy >= board.height as i32
+                            || board.is_occupied(xx as usize, yy as usize)
+                        {
+                            is_valid = false;
+                            break;
                         }
+
+                        mask |= board.cell_bit(xx as usize, yy as usize);
+                    }
+
+                    if is_valid {
+                        allowed_masks.push(mask);
                     }
                 }
             }
         }
 
-        self.allowed_placements = allowed_placements;
+        // Sorted, so that `covers_exactly` can search them.
+        allowed_masks.sort_unstable();
+        self.allowed_masks = allowed_masks;
+    }
+
+    /// Gets the occupancy masks of the allowed placements for this piece.
+    pub fn get_allowed_masks(&self) -> &[u64] {
+        &self.allowed_masks
+    }
+
+    /// Checks whether some allowed placement of this piece covers exactly the cells in the mask.
+    pub fn covers_exactly(&self, mask: u64) -> bool {
+        self.allowed_masks.binary_search(&mask).is_ok()
+    }
+}
+
+/// Gets the four rotations of a shape, then the four rotations of its mirror image, each normalized.
+fn transformations_of(shape: &[Coordinates]) -> Vec<Vec<Coordinates>> {
+    let mirrored: Vec<Coordinates> = shape.iter().map(|&(x, y)| (-x, y)).collect(); // Flip horizontally
+    [shape.to_vec(), mirrored]
+        .into_iter()
+        .flat_map(|start| {
+            (0..4).scan(start, |current_shape, _| {
+                let result = normalize(current_shape);
+                *current_shape = current_shape
+                    .iter()
+                    .map(|(x, y)| (*y, -*x)) // Rotate 90° clockwise
+                    .collect();
+                Some(result)
+            })
+        })
+        .collect()
+}
+
+/// Gets the width and height of a normalized shape.
+pub fn dimensions_of(shape: &[Coordinates]) -> (i32, i32) {
+    shape.iter().fold((0, 0), |(max_x, max_y), &(x, y)| {
+        (max_x.max(x + 1), max_y.max(y + 1))
+    })
+}
+
+/// Moves a shape so its top-left corner is at the origin, with its cells in order,
+/// so that two shapes are the same if and only if they look the same.
+fn normalize(shape: &[Coordinates]) -> Vec<Coordinates> {
+    let min_x = shape.iter().map(|&(x, _)| x).min().unwrap_or(0);
+    let min_y = shape.iter().map(|&(_, y)| y).min().unwrap_or(0);
+    let mut normalized: Vec<Coordinates> =
+        shape.iter().map(|&(x, y)| (x - min_x, y - min_y)).collect();
+    normalized.sort_unstable();
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn piece(shape: Vec<Coordinates>, flippable: bool) -> Piece {
+        Piece::new('A', shape, (0, 0, 0), (0, 0, 0), flippable)
+    }
+
+    #[test]
+    fn test_symmetry() {
+        let f = piece(vec![(0, 0), (1, 0), (1, 1), (1, 2), (2, 1)], true);
+        assert_eq!(f.symmetry(), Symmetry::None);
+        assert_eq!(f.orientations().len(), 8);
+
+        let t = piece(vec![(0, 0), (1, 0), (2, 0), (1, 1), (1, 2)], true);
+        assert_eq!(t.symmetry(), Symmetry::Mirror);
+        assert_eq!(t.orientations().len(), 4);
+
+        let z = piece(vec![(0, 0), (1, 0), (1, 1), (1, 2), (2, 2)], false);
+        assert_eq!(z.symmetry(), Symmetry::HalfTurn);
+        assert_eq!(z.orientations().len(), 2);
+
+        let i = piece(vec![(0, 0), (1, 0), (2, 0), (3, 0), (4, 0)], false);
+        assert_eq!(i.symmetry(), Symmetry::MirrorAndHalfTurn);
+        assert_eq!(i.orientations().len(), 2);
+
+        let square = piece(vec![(0, 0), (1, 0), (0, 1), (1, 1)], true);
+        assert_eq!(square.symmetry(), Symmetry::Full);
+        assert_eq!(square.orientations().len(), 1);
+    }
+
+    #[test]
+    fn test_orientations() {
+        let l = piece(vec![(0, 0), (1, 0), (2, 0), (3, 0), (0, 1)], false);
+        assert_eq!(
+            l.orientations(),
+            &[
+                vec![(0, 0), (0, 1), (1, 0), (2, 0), (3, 0)],
+                vec![(0, 0), (0, 1), (0, 2), (0, 3), (1, 3)],
+                vec![(0, 1), (1, 1), (2, 1), (3, 0), (3, 1)],
+                vec![(0, 0), (1, 0), (1, 1), (1, 2), (1, 3)],
+            ]
+        );
+        assert_eq!(l.get_shape(Rotation::Zero, false), &l.orientations()[0]);
+
+        let mut i = piece(vec![(0, 0), (1, 0), (2, 0), (3, 0), (4, 0)], false);
+        assert_eq!(
+            i.get_shape(Rotation::OneEighty, false),
+            &i.orientations()[0]
+        );
+        i.set_flippable(true);
+        assert_eq!(i.orientations().len(), 2);
     }
 
-    /// Gets the allowed positions for this piece.
-    pub fn get_allowed_placements(&self) -> &Vec<Placement> {
-        &self.allowed_placements
+    #[test]
+    fn test_allowed_masks_are_distinct() {
+        let mut i = piece(vec![(0, 0), (1, 0), (2, 0), (3, 0), (4, 0)], true);
+        i.precompute_allowed_placements(&Board::new(5, 5, '·'));
+        let mut masks = i.get_allowed_masks().to_vec();
+        assert_eq!(masks.len(), 10);
+        masks.sort_unstable();
+        masks.dedup();
+        assert_eq!(masks.len(), 10);
     }
 }
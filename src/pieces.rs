@@ -1,33 +1,225 @@
-use crate::{board::Board, piece::Piece};
-use once_cell::sync::Lazy;
-use std::collections::HashMap;
+use crate::{
+    board::Board,
+    piece::{Coordinates, Piece},
+};
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::Path;
 
-pub static PIECES: Lazy<HashMap<char, Piece>> = Lazy::new(|| {
-    let mut pieces = HashMap::new();
+/// A piece set that could not be read or parsed, or does not fit its board.
+#[derive(Debug)]
+pub enum PieceSetError {
+    /// The file could not be read
+    Io(io::Error),
+    /// The file has no pieces
+    Empty,
+    /// A line that ends before one of its fields
+    MissingField {
+        /// The line of the file, from 1
+        line: usize,
+        /// The missing field
+        field: &'static str,
+    },
+    /// A symbol that is not a single uppercase letter
+    InvalidSymbol {
+        /// The line of the file, from 1
+        line: usize,
+        /// The symbol
+        symbol: String,
+    },
+    /// A color that is not `#rrggbb`
+    InvalidColor {
+        /// The line of the file, from 1
+        line: usize,
+        /// The color
+        color: String,
+    },
+    /// Neither `flippable` nor `one-sided`
+    InvalidMirroring {
+        /// The line of the file, from 1
+        line: usize,
+        /// What was there instead
+        value: String,
+    },
+    /// A cell that is not an `x,y` pair
+    InvalidCell {
+        /// The line of the file, from 1
+        line: usize,
+        /// The cell
+        cell: String,
+    },
+    /// A cell given twice for the same piece
+    DuplicateCell {
+        /// The line of the file, from 1
+        line: usize,
+        /// The cell
+        cell: String,
+    },
+    /// A piece whose cells are not all connected
+    Disconnected(char),
+    /// A symbol used by more than one piece
+    DuplicateSymbol(char),
+    /// Pieces that cover a different number of cells than the board leaves open
+    AreaMismatch {
+        /// The cells the pieces cover
+        pieces: usize,
+        /// The open cells on the board
+        cells: usize,
+    },
+}
 
-    let defaults = Pieces::get_defaults_for_board(&Board::new(9, 6, '·'));
+impl std::error::Error for PieceSetError {}
 
-    for piece in defaults.iter() {
-        pieces.insert(piece.display_symbol, piece.clone());
+impl std::fmt::Display for PieceSetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PieceSetError::Io(e) => write!(f, "{}", e),
+            PieceSetError::Empty => write!(f, "The piece set has no pieces"),
+            PieceSetError::MissingField { line, field } => {
+                write!(f, "Line {}: missing the {}", line, field)
+            }
+            PieceSetError::InvalidSymbol { line, symbol } => write!(
+                f,
+                "Line {}: \"{}\" is not a single uppercase letter",
+                line, symbol
+            ),
+            PieceSetError::InvalidColor { line, color } => {
+                write!(f, "Line {}: \"{}\" is not a #rrggbb color", line, color)
+            }
+            PieceSetError::InvalidMirroring { line, value } => write!(
+                f,
+                "Line {}: expected \"flippable\" or \"one-sided\", found \"{}\"",
+                line, value
+            ),
+            PieceSetError::InvalidCell { line, cell } => {
+                write!(f, "Line {}: \"{}\" is not an x,y cell", line, cell)
+            }
+            PieceSetError::DuplicateCell { line, cell } => {
+                write!(f, "Line {}: cell {} appears more than once", line, cell)
+            }
+            PieceSetError::Disconnected(symbol) => write!(f, "Piece {} is not connected", symbol),
+            PieceSetError::DuplicateSymbol(symbol) => {
+                write!(f, "Piece {} appears more than once", symbol)
+            }
+            PieceSetError::AreaMismatch { pieces, cells } => write!(
+                f,
+                "The pieces cover {} cells, but the board has {} open cells",
+                pieces, cells
+            ),
+        }
     }
+}
 
-    pieces.insert('m', Pieces::get_month());
-    pieces.insert('d', Pieces::get_day());
-    pieces.insert('w', Pieces::get_weekday());
-    pieces.insert('x', Pieces::get_corner());
-
-    pieces
-});
+impl From<io::Error> for PieceSetError {
+    fn from(e: io::Error) -> Self {
+        PieceSetError::Io(e)
+    }
+}
 
+/// The built-in piece sets and the pieces for a day's cells, and loading piece set files.
 pub struct Pieces;
 
 impl Pieces {
-    pub fn by_symbol(symbol: char) -> &'static Piece {
-        &PIECES[&symbol]
+    /// Loads a piece set from a file.
+    pub fn load(path: &Path) -> Result<Vec<Piece>, PieceSetError> {
+        Self::parse(&fs::read_to_string(path)?)
     }
 
+    /// Parses a piece set, one piece per line: a symbol (an uppercase letter), foreground and
+    /// background colors as `#rrggbb`, `flippable` or `one-sided`, then the piece's cells as
+    /// `x,y` pairs. Blank lines and lines starting with `//` are ignored.
+    pub fn parse(text: &str) -> Result<Vec<Piece>, PieceSetError> {
+        let mut pieces: Vec<Piece> = Vec::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let line_number = i + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with("//") {
+                continue;
+            }
+
+            let mut tokens = line.split_whitespace();
+            let mut next = |field| {
+                tokens.next().ok_or(PieceSetError::MissingField {
+                    line: line_number,
+                    field,
+                })
+            };
+
+            let symbol = next("symbol")?;
+            let symbol = match symbol.chars().collect::<Vec<_>>()[..] {
+                [c] if c.is_ascii_uppercase() => c,
+                _ => {
+                    return Err(PieceSetError::InvalidSymbol {
+                        line: line_number,
+                        symbol: symbol.to_string(),
+                    })
+                }
+            };
+            let color = parse_color(next("foreground color")?, line_number)?;
+            let bg = parse_color(next("background color")?, line_number)?;
+            let flippable = match next("mirroring")? {
+                "flippable" => true,
+                "one-sided" => false,
+                value => {
+                    return Err(PieceSetError::InvalidMirroring {
+                        line: line_number,
+                        value: value.to_string(),
+                    })
+                }
+            };
+
+            let mut shape: Vec<Coordinates> = Vec::new();
+            for cell in tokens {
+                let coordinates = parse_cell(cell).ok_or(PieceSetError::InvalidCell {
+                    line: line_number,
+                    cell: cell.to_string(),
+                })?;
+                if shape.contains(&coordinates) {
+                    return Err(PieceSetError::DuplicateCell {
+                        line: line_number,
+                        cell: cell.to_string(),
+                    });
+                }
+                shape.push(coordinates);
+            }
+            if shape.is_empty() {
+                return Err(PieceSetError::MissingField {
+                    line: line_number,
+                    field: "cells",
+                });
+            }
+            if !is_connected(&shape) {
+                return Err(PieceSetError::Disconnected(symbol));
+            }
+            if pieces.iter().any(|p| p.symbol == symbol) {
+                return Err(PieceSetError::DuplicateSymbol(symbol));
+            }
+
+            pieces.push(Piece::new(symbol, shape, color, bg, flippable));
+        }
+
+        match pieces.is_empty() {
+            true => Err(PieceSetError::Empty),
+            false => Ok(pieces),
+        }
+    }
+
+    /// Gets the Calendarium pieces, with their allowed placements precomputed for a board.
     pub fn get_defaults_for_board(board: &Board) -> [Piece; 10] {
-        let mut pieces = [
+        let mut pieces = Self::get_defaults();
+
+        for piece in &mut pieces {
+            piece.precompute_allowed_placements(board);
+        }
+
+        pieces
+    }
+
+    /// Gets the ten pieces of the Calendarium puzzle, without any precomputed placements.
+    pub fn get_defaults() -> [Piece; 10] {
+        [
             // F F
             //   F F
             //   F
@@ -125,36 +317,217 @@ impl Pieces {
                 (64, 140, 86),   // Green background
                 false,           // Not flippable
             ),
-        ];
-
-        for piece in &mut pieces {
-            piece.precompute_allowed_placements(&board);
-        }
+        ]
+    }
 
-        pieces
+    /// Gets the eight pieces of DragonFjord's A-Puzzle-A-Day, without any precomputed placements.
+    /// Every piece is flippable, since the physical pieces can be turned over.
+    pub fn get_a_puzzle_a_day() -> [Piece; 8] {
+        [
+            // O O O
+            // O O O
+            Piece::new(
+                'O',
+                vec![(0, 0), (1, 0), (2, 0), (0, 1), (1, 1), (2, 1)],
+                (0, 0, 0),       // Black foreground
+                (153, 204, 255), // Light blue background
+                true,            // Flippable
+            ),
+            // U U
+            // U
+            // U U
+            Piece::new(
+                'U',
+                vec![(0, 0), (1, 0), (0, 1), (0, 2), (1, 2)],
+                (0, 0, 0),       // Black foreground
+                (239, 235, 231), // Light beige background
+                true,            // Flippable
+            ),
+            // Z
+            // Z Z Z
+            //     Z
+            Piece::new(
+                'Z',
+                vec![(0, 0), (1, 0), (1, 1), (1, 2), (2, 2)],
+                (120, 60, 60),   // Dark reddish-brown foreground
+                (255, 255, 255), // White background
+                true,            // Flippable
+            ),
+            // L L L L
+            // L
+            Piece::new(
+                'L',
+                vec![(0, 0), (1, 0), (2, 0), (3, 0), (0, 1)],
+                (255, 255, 255), // White foreground
+                (0, 102, 204),   // Blue background
+                true,            // Flippable
+            ),
+            // V
+            // V
+            // V V V
+            Piece::new(
+                'V',
+                vec![(0, 0), (0, 1), (0, 2), (1, 2), (2, 2)],
+                (255, 255, 255), // White foreground
+                (88, 28, 71),    // Deep magenta background
+                true,            // Flippable
+            ),
+            // N
+            // N N
+            //   N
+            //   N
+            Piece::new(
+                'N',
+                vec![(0, 0), (0, 1), (1, 1), (1, 2), (1, 3)],
+                (255, 255, 255), // White foreground
+                (102, 204, 153), // Soft teal background
+                true,            // Flippable
+            ),
+            // Y Y Y Y
+            //   Y
+            Piece::new(
+                'Y',
+                vec![(0, 0), (1, 0), (2, 0), (3, 0), (1, 1)],
+                (255, 255, 255), // White foreground
+                (204, 102, 255), // Light purple background
+                true,            // Flippable
+            ),
+            // P
+            // P P
+            // P P
+            Piece::new(
+                'P',
+                vec![(0, 0), (0, 1), (1, 1), (0, 2), (1, 2)],
+                (255, 255, 255), // White foreground
+                (204, 102, 0),   // Orange background
+                true,            // Flippable
+            ),
+        ]
     }
 
+    /// Gets the piece that covers the month's cell.
     pub fn get_month() -> Piece {
         let mut piece = Piece::new('m', vec![(0, 0)], (255, 255, 255), (0, 0, 0), false);
         piece.display_as('☻');
         piece
     }
 
+    /// Gets the piece that covers the day of the month's cell.
     pub fn get_day() -> Piece {
         let mut piece = Piece::new('d', vec![(0, 0)], (255, 255, 255), (0, 0, 0), false);
         piece.display_as('◉');
         piece
     }
 
+    /// Gets the piece that covers the weekday's cell.
     pub fn get_weekday() -> Piece {
         let mut piece = Piece::new('w', vec![(0, 0)], (255, 255, 255), (0, 0, 0), false);
         piece.display_as('☼');
         piece
     }
 
+    /// Gets the piece that covers the year's cell.
+    pub fn get_year() -> Piece {
+        let mut piece = Piece::new('y', vec![(0, 0)], (255, 255, 255), (0, 0, 0), false);
+        piece.display_as('◆');
+        piece
+    }
+
+    /// Gets the piece that fills a cell that is not part of the board.
     pub fn get_corner() -> Piece {
         let mut piece = Piece::new('x', vec![(0, 0)], (255, 255, 255), (0, 0, 0), false);
         piece.display_as(' ');
         piece
     }
 }
+
+/// Parses a `#rrggbb` color.
+fn parse_color(token: &str, line: usize) -> Result<(u8, u8, u8), PieceSetError> {
+    let hex = token
+        .strip_prefix('#')
+        .filter(|hex| hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()));
+    let channel = |i: usize| u8::from_str_radix(&hex?[2 * i..2 * i + 2], 16).ok();
+    match (channel(0), channel(1), channel(2)) {
+        (Some(r), Some(g), Some(b)) => Ok((r, g, b)),
+        _ => Err(PieceSetError::InvalidColor {
+            line,
+            color: token.to_string(),
+        }),
+    }
+}
+
+/// Parses an `x,y` cell with non-negative coordinates.
+fn parse_cell(token: &str) -> Option<Coordinates> {
+    let (x, y) = token.split_once(',')?;
+    let (x, y) = (x.parse::<u8>().ok()?, y.parse::<u8>().ok()?);
+    Some((x as i32, y as i32))
+}
+
+/// Checks whether the cells of a shape are all joined by their edges.
+fn is_connected(shape: &[Coordinates]) -> bool {
+    let cells: HashSet<Coordinates> = shape.iter().copied().collect();
+    let mut reached = HashSet::from([shape[0]]);
+    let mut frontier = vec![shape[0]];
+    while let Some((x, y)) = frontier.pop() {
+        for neighbor in [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)] {
+            if cells.contains(&neighbor) && reached.insert(neighbor) {
+                frontier.push(neighbor);
+            }
+        }
+    }
+    reached.len() == cells.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::piece::Rotation;
+
+    #[test]
+    fn test_parse_matches_defaults() {
+        let parsed = Pieces::parse(include_str!("../pieces/calendarium.txt")).unwrap();
+        let defaults = Pieces::get_defaults();
+        assert_eq!(parsed.len(), defaults.len());
+        for (parsed, default) in parsed.iter().zip(defaults.iter()) {
+            assert_eq!(parsed.symbol, default.symbol);
+            assert_eq!(parsed.flippable, default.flippable);
+            assert_eq!((parsed.color, parsed.bg), (default.color, default.bg));
+            assert_eq!(
+                parsed.get_shape(Rotation::Zero, false),
+                default.get_shape(Rotation::Zero, false)
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(matches!(
+            Pieces::parse("// nothing"),
+            Err(PieceSetError::Empty)
+        ));
+        assert!(matches!(
+            Pieces::parse("f #000000 #ffffff flippable 0,0"),
+            Err(PieceSetError::InvalidSymbol { line: 1, .. })
+        ));
+        assert!(matches!(
+            Pieces::parse("F #000000 #fffff flippable 0,0"),
+            Err(PieceSetError::InvalidColor { line: 1, .. })
+        ));
+        assert!(matches!(
+            Pieces::parse("F #000000 #ffffff maybe 0,0"),
+            Err(PieceSetError::InvalidMirroring { line: 1, .. })
+        ));
+        assert!(matches!(
+            Pieces::parse("F #000000 #ffffff flippable"),
+            Err(PieceSetError::MissingField { field: "cells", .. })
+        ));
+        assert!(matches!(
+            Pieces::parse("F #000000 #ffffff flippable 0,0 2,0"),
+            Err(PieceSetError::Disconnected('F'))
+        ));
+        assert!(matches!(
+            Pieces::parse("F #000000 #ffffff flippable 0,0\nF #000000 #ffffff flippable 0,0"),
+            Err(PieceSetError::DuplicateSymbol('F'))
+        ));
+    }
+}
@@ -0,0 +1,288 @@
+use crate::{
+    board::{Board, SolutionCount},
+    cache::SolutionCache,
+    calendar::Day,
+    error::{Error, Result},
+    layout::{fnv1a, Layout},
+    piece::{Piece, Rotation},
+    pieces::{PieceSetError, Pieces},
+    solver::{SolveOptions, Solver, Strategy},
+    stream::CancellationToken,
+};
+use std::time::Duration;
+
+/// A board layout, together with the pieces that are placed on it.
+#[derive(Debug, Clone)]
+pub struct Puzzle {
+    /// The board layout
+    pub layout: Layout,
+    pieces: Vec<Piece>, // Without precomputed placements, since those depend on the day
+}
+
+impl Puzzle {
+    /// Creates a puzzle from a layout and its pieces.
+    pub fn new(layout: Layout, pieces: Vec<Piece>) -> Self {
+        Puzzle { layout, pieces }
+    }
+
+    /// Gets the Calendarium puzzle: a 9×6 board with weekdays, and ten one-sided pentominoes.
+    pub fn calendarium() -> Self {
+        Self::new(Layout::calendarium(), Pieces::get_defaults().to_vec())
+    }
+
+    /// Gets DragonFjord's A-Puzzle-A-Day: a 7×7 board without weekdays, and eight two-sided pieces.
+    pub fn a_puzzle_a_day() -> Self {
+        Self::new(
+            Layout::a_puzzle_a_day(),
+            Pieces::get_a_puzzle_a_day().to_vec(),
+        )
+    }
+
+    /// Replaces the layout, keeping the pieces.
+    pub fn with_layout(self, layout: Layout) -> Self {
+        Self::new(layout, self.pieces)
+    }
+
+    /// Gets the pieces, without any precomputed placements.
+    pub fn pieces(&self) -> &[Piece] {
+        &self.pieces
+    }
+
+    /// Replaces the pieces, keeping the layout.
+    pub fn with_pieces(self, pieces: Vec<Piece>) -> Self {
+        Self::new(self.layout, pieces)
+    }
+
+    /// Lets every piece be flipped over.
+    pub fn with_flips(mut self) -> Self {
+        for piece in &mut self.pieces {
+            piece.set_flippable(true);
+        }
+        self
+    }
+
+    /// Checks that the pieces cover exactly as many cells as the board leaves open.
+    pub fn check_area(&self) -> Result<()> {
+        let pieces = self.pieces.iter().map(|p| p.size()).sum();
+        let cells = self.layout.open_cells();
+        match pieces == cells {
+            true => Ok(()),
+            false => Err(Error::PiecesDoNotFit(PieceSetError::AreaMismatch {
+                pieces,
+                cells,
+            })),
+        }
+    }
+
+    /// Builds the board to solve for a day: the layout with the day's cells covered.
+    pub fn board_for(&self, day: &Day) -> Result<Board> {
+        Ok(Board::make(&self.layout, day)?)
+    }
+
+    /// Streams every solution for a day as it is found, searching on a background thread
+    /// with the fastest backtracking strategy. Dropping the stream stops the search.
+    pub fn solutions(&self, day: &Day) -> Result<Solutions> {
+        let options = SolveOptions {
+            strategy: Strategy::CellFirst,
+            all: true,
+            ..SolveOptions::default()
+        };
+        self.solve(day, &options, None)
+    }
+
+    /// Gets the solutions for a day: the precomputed ones from `cache` if it has the day,
+    /// or else the ones a search finds, streamed from a background thread as it finds them.
+    /// Unless `options.all` is set, only the first solution is given.
+    pub fn solve(
+        &self,
+        day: &Day,
+        options: &SolveOptions,
+        cache: Option<&SolutionCache>,
+    ) -> Result<Solutions> {
+        let limit = if options.all { usize::MAX } else { 1 };
+        if let Some(boards) = cache.and_then(|c| c.solutions_for(day)) {
+            return Ok(Solutions {
+                boards: Box::new(boards.into_iter()),
+                remaining: limit,
+                search: None,
+            });
+        }
+
+        let board = self.board_for(day)?;
+        let pieces = self.pieces_for_board(&board);
+        let pieces: Vec<&Piece> = pieces.iter().collect();
+        let stream = match options.solver {
+            Solver::Backtrack => board.stream_boards_placing_all_pieces(
+                &pieces,
+                options.all,
+                options.deterministic,
+                options.strategy,
+                options.stats.clone(),
+            ),
+            Solver::Dlx => board.stream_boards_placing_all_pieces_dlx(
+                &pieces,
+                options.all,
+                options.deterministic,
+            ),
+        };
+
+        let token = stream.cancellation_token();
+        if let Some(timeout) = options.timeout {
+            token.cancel_after(timeout);
+        }
+        Ok(Solutions {
+            boards: Box::new(stream),
+            remaining: limit,
+            search: Some((token, options.timeout)),
+        })
+    }
+
+    /// Counts the solutions for a day, without building them, using the count in `cache`
+    /// if it has the day. With `breakdown`, the count is also split by where the first piece
+    /// goes, which always takes a search. Only the solver and strategy of `options` are used.
+    pub fn count(
+        &self,
+        day: &Day,
+        options: &SolveOptions,
+        breakdown: bool,
+        cache: Option<&SolutionCache>,
+    ) -> Result<SolutionCount> {
+        if let Some(total) = cache.and_then(|c| c.count_for(day)).filter(|_| !breakdown) {
+            return Ok(SolutionCount {
+                total,
+                by_first_placement: Vec::new(),
+            });
+        }
+
+        let board = self.board_for(day)?;
+        let pieces = self.pieces_for_board(&board);
+        let pieces: Vec<&Piece> = pieces.iter().collect();
+        Ok(match (options.solver, options.strategy) {
+            (Solver::Backtrack, Strategy::PieceFirst) => {
+                board.count_boards_placing_all_pieces(&pieces, breakdown)
+            }
+            (Solver::Backtrack, Strategy::CellFirst) => {
+                board.count_boards_placing_all_pieces_cell_first(&pieces, breakdown)
+            }
+            (Solver::Dlx, _) => board.count_boards_placing_all_pieces_dlx(&pieces, breakdown),
+        })
+    }
+
+    /// Gets the pieces with their allowed placements precomputed for a board.
+    pub fn pieces_for_board(&self, board: &Board) -> Vec<Piece> {
+        let mut pieces = self.pieces.clone();
+        for piece in &mut pieces {
+            piece.precompute_allowed_placements(board);
+        }
+        pieces
+    }
+
+    /// Gets a fingerprint of the layout and pieces, which a solution database is checked against.
+    pub fn fingerprint(&self) -> u64 {
+        // The layout's fingerprint, then each piece's symbol, flippability and shape.
+        let bytes =
+            self.layout
+                .fingerprint()
+                .to_le_bytes()
+                .into_iter()
+                .chain(self.pieces.iter().flat_map(|piece| {
+                    let shape = piece.get_shape(Rotation::Zero, false);
+                    [piece.symbol as u8, piece.flippable as u8]
+                        .into_iter()
+                        .chain(shape.iter().flat_map(|&(x, y)| [x as u8, y as u8]))
+                        .chain([u8::MAX])
+                }));
+
+        fnv1a(bytes)
+    }
+}
+
+/// The solutions `Puzzle::solve` gives for a day, as an iterator of boards.
+pub struct Solutions {
+    boards: Box<dyn Iterator<Item = Board> + Send>,
+    remaining: usize, // How many more solutions were asked for
+    search: Option<(CancellationToken, Option<Duration>)>, // None if they were precomputed
+}
+
+impl Solutions {
+    /// Checks whether the solutions come from a search, rather than being precomputed.
+    pub fn searched(&self) -> bool {
+        self.search.is_some()
+    }
+
+    /// Gets a token that cancels the search, if there is one.
+    pub fn cancellation_token(&self) -> Option<CancellationToken> {
+        self.search.as_ref().map(|(token, _)| token.clone())
+    }
+
+    /// Checks how the search went, once the solutions have been read: it is an error
+    /// if it timed out or panicked, since then there may be solutions it did not give.
+    /// Once every solution that was asked for has been given, the search is not needed.
+    pub fn outcome(&self) -> Result<()> {
+        if self.remaining == 0 {
+            return Ok(());
+        }
+        match &self.search {
+            Some((token, _)) if token.has_panicked() => Err(Error::SearchPanicked),
+            Some((token, timeout)) if token.is_cancelled() => {
+                Err(Error::TimedOut(timeout.unwrap_or_default()))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Iterator for Solutions {
+    type Item = Board;
+
+    fn next(&mut self) -> Option<Board> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let board = self.boards.next()?;
+        self.remaining -= 1;
+        Some(board)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::march_3_tuesday;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_solve() {
+        let known = march_3_tuesday();
+        let puzzle = Puzzle::calendarium();
+        let serialized =
+            |solutions: Solutions| -> Vec<String> { solutions.map(|b| b.serialize()).collect() };
+
+        // A search gives only the first solution, unless all of them are asked for.
+        let first = puzzle
+            .solve(&known.day, &SolveOptions::default(), None)
+            .unwrap();
+        assert!(first.searched());
+        let first = serialized(first);
+        assert_eq!(first.len(), 1);
+        assert!(known.solutions.contains(&first[0]));
+
+        let options = SolveOptions {
+            solver: Solver::Dlx,
+            all: true,
+            deterministic: true,
+            ..SolveOptions::default()
+        };
+        let all = puzzle.solve(&known.day, &options, None).unwrap();
+        assert_eq!(serialized(all), known.solutions);
+
+        // Precomputed solutions are used instead of searching.
+        let cache = SolutionCache::Tree(PathBuf::from("solutions"), puzzle.clone());
+        let cached = puzzle.solve(&known.day, &options, Some(&cache)).unwrap();
+        assert!(!cached.searched());
+        assert_eq!(serialized(cached), known.solutions);
+
+        let count = puzzle.count(&known.day, &options, false, None).unwrap();
+        assert_eq!(count.total, known.solutions.len());
+    }
+}
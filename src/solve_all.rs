@@ -0,0 +1,164 @@
+use crate::{
+    board::Board,
+    calendar::{Day, Month, MonthDay, Weekday},
+    error::{Error, Result},
+    layout::Layout,
+    piece::Piece,
+    puzzle::Puzzle,
+    solver::{Solver, Strategy},
+    stats::SearchStats,
+};
+use chrono::{Datelike, NaiveDate};
+use rayon::prelude::*;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::Mutex;
+use strum::IntoEnumIterator;
+
+/// What a `solve_all` run did.
+#[derive(Debug, Default)]
+pub struct SolveAllSummary {
+    /// Dates solved and written by this run
+    pub solved: usize,
+    /// Dates that already had a solutions file
+    pub skipped: usize,
+}
+
+/// Lists every date challenge on a layout: each day of each month,
+/// on each day of the week if the layout has weekdays.
+/// A layout with years only has the actual dates of those years, with their real weekdays.
+pub fn all_days(layout: &Layout) -> Vec<Day> {
+    if layout.has_years() {
+        return layout
+            .years()
+            .into_iter()
+            .filter_map(|year| NaiveDate::from_ymd_opt(year, 1, 1))
+            .flat_map(|first| {
+                first
+                    .iter_days()
+                    .take_while(move |d| d.year() == first.year())
+            })
+            .filter_map(|date| Day::from_date(date).ok())
+            .map(|day| day.for_layout(layout))
+            .collect();
+    }
+
+    let weekdays: Vec<Option<Weekday>> = match layout.has_weekdays() {
+        true => Weekday::iter().map(Some).collect(),
+        false => vec![None],
+    };
+
+    Month::iter()
+        .flat_map(|month| {
+            let weekdays = &weekdays;
+            (1..=month.day_count()).flat_map(move |day| {
+                let month = month.clone();
+                weekdays.iter().map(move |weekday| {
+                    Day::new(month.clone(), MonthDay::new(day).unwrap(), weekday.clone()).unwrap()
+                })
+            })
+        })
+        .collect()
+}
+
+/// Gets the path of the solutions file for a day, as `<Month>/<day>/<Weekday>.txt` under `root`,
+/// or `<Month>/<day>.txt` for a day without a weekday, under a `<year>` directory for a day with one.
+pub fn solution_path(root: &Path, day: &Day) -> PathBuf {
+    let root = match day.year {
+        Some(year) => root.join(year.to_string()),
+        None => root.to_path_buf(),
+    };
+    let month = root.join(day.month.to_string());
+    match &day.weekday {
+        Some(weekday) => month
+            .join(day.day.to_string())
+            .join(format!("{}.txt", weekday)),
+        None => month.join(format!("{}.txt", day.day)),
+    }
+}
+
+/// Finds every solution for a day of a puzzle, sorted by `serialize()`.
+/// Backtracking counts what it does in `stats`.
+pub fn solve_day(
+    puzzle: &Puzzle,
+    day: &Day,
+    solver: Solver,
+    strategy: Strategy,
+    stats: &SearchStats,
+) -> Result<Vec<Board>> {
+    let board = puzzle.board_for(day)?;
+    let default_pieces = puzzle.pieces_for_board(&board);
+    let pieces: Vec<&Piece> = default_pieces.iter().collect();
+    let stop = AtomicBool::new(false);
+
+    let solutions = Mutex::new(Vec::new());
+    let on_solution = |b: Board| {
+        solutions.lock().unwrap().push(b);
+        true
+    };
+    match solver {
+        Solver::Backtrack => board.find_boards_placing_all_pieces_with(
+            strategy,
+            &pieces,
+            &on_solution,
+            &stop,
+            stats,
+            true,
+        ),
+        Solver::Dlx => board.find_boards_placing_all_pieces_dlx(&pieces, &on_solution, &stop, true),
+    }
+
+    let mut solutions = solutions.into_inner().unwrap();
+    solutions.sort_by_key(|b| b.serialize());
+    Ok(solutions)
+}
+
+/// Solves every date challenge in parallel, writing one file of raw solutions per date under `root`.
+/// Dates that already have a solutions file are skipped, so an interrupted run can be resumed.
+/// Calls `on_solved` with each date once its file is written, from whichever thread solved it.
+pub fn solve_all<F>(
+    root: &Path,
+    puzzle: &Puzzle,
+    solver: Solver,
+    strategy: Strategy,
+    on_solved: F,
+) -> Result<SolveAllSummary>
+where
+    F: Fn(&Day) + Sync,
+{
+    let written: Vec<bool> = all_days(&puzzle.layout)
+        .par_iter()
+        .map(|day| {
+            let path = solution_path(root, day);
+            if path.exists() {
+                return Ok(false);
+            }
+
+            let contents: String =
+                solve_day(puzzle, day, solver, strategy, &SearchStats::default())?
+                    .iter()
+                    .map(|b| b.serialize() + "\n")
+                    .collect();
+
+            // Write to a temporary file first, so a partial file is never mistaken for a finished one.
+            let temporary = path.with_extension("txt.tmp");
+            let write = || -> io::Result<()> {
+                fs::create_dir_all(path.parent().unwrap())?;
+                fs::write(&temporary, contents)?;
+                fs::rename(&temporary, &path)
+            };
+            write().map_err(|e| Error::Io(path.clone(), e))?;
+
+            on_solved(day);
+            Ok(true)
+        })
+        .collect::<Result<_>>()?;
+
+    let solved = written.iter().filter(|&&w| w).count();
+    Ok(SolveAllSummary {
+        solved,
+        skipped: written.len() - solved,
+    })
+}
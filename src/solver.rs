@@ -0,0 +1,40 @@
+use crate::stats::SearchStats;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The algorithm used to search for solutions
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Solver {
+    /// Parallel backtracking, placing pieces one at a time
+    #[default]
+    Backtrack,
+    /// Knuth's Dancing Links (Algorithm X) exact cover solver
+    Dlx,
+}
+
+/// The order in which the backtracking solver fills the board
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Strategy {
+    /// Place the pieces one at a time, trying every placement of each
+    #[default]
+    PieceFirst,
+    /// Fill the open cell with the fewest placements left, trying every piece that covers it
+    CellFirst,
+}
+
+/// How `Puzzle::solve` and `Puzzle::count` look for solutions.
+#[derive(Clone, Debug, Default)]
+pub struct SolveOptions {
+    /// The algorithm to search with
+    pub solver: Solver,
+    /// The search order, for the backtrack solver
+    pub strategy: Strategy,
+    /// Whether to give every solution, rather than only the first one found
+    pub all: bool,
+    /// Whether to give solutions in `serialize()` order, so the first is always the same
+    pub deterministic: bool,
+    /// How long the search may run before it is cancelled
+    pub timeout: Option<Duration>,
+    /// What the backtrack solver does, which can be watched while it runs
+    pub stats: Arc<SearchStats>,
+}
@@ -0,0 +1,195 @@
+use crate::board::MAX_CELLS;
+use std::io::{IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// The deepest a search can go, with one piece placed per level.
+const MAX_DEPTH: usize = MAX_CELLS + 1;
+
+/// A rule that showed a blank region could not be filled by the remaining pieces.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Prune {
+    /// No combination of the remaining pieces has as many cells as the region
+    Size,
+    /// The region is one piece big, and no remaining piece fits it exactly
+    Shape,
+    /// The region is one or two pieces big, and no one or two remaining pieces fill it
+    Pair,
+}
+
+/// Counts of what a backtracking search did, shared between its threads.
+#[derive(Debug)]
+pub struct SearchStats {
+    /// Boards visited, by number of pieces placed
+    pub nodes_by_depth: [AtomicUsize; MAX_DEPTH],
+    /// Placements tried that fit on the board
+    pub placements: AtomicUsize,
+    /// Placements pruned by [`Prune::Size`]
+    pub pruned_by_size: AtomicUsize,
+    /// Placements pruned by [`Prune::Shape`]
+    pub pruned_by_shape: AtomicUsize,
+    /// Placements pruned by [`Prune::Pair`]
+    pub pruned_by_pair: AtomicUsize,
+    /// Solutions found
+    pub solutions: AtomicUsize,
+    /// Top-level branches of the search
+    pub branches: AtomicUsize,
+    /// Top-level branches searched to the end
+    pub branches_done: AtomicUsize,
+}
+
+impl Default for SearchStats {
+    fn default() -> Self {
+        SearchStats {
+            nodes_by_depth: std::array::from_fn(|_| AtomicUsize::new(0)),
+            placements: AtomicUsize::new(0),
+            pruned_by_size: AtomicUsize::new(0),
+            pruned_by_shape: AtomicUsize::new(0),
+            pruned_by_pair: AtomicUsize::new(0),
+            solutions: AtomicUsize::new(0),
+            branches: AtomicUsize::new(0),
+            branches_done: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl SearchStats {
+    /// Counts a board visited with `depth` pieces placed.
+    pub fn record_node(&self, depth: usize) {
+        self.nodes_by_depth[depth].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Counts a placement that fit on the board.
+    pub fn record_placement(&self) {
+        self.placements.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Counts a placement abandoned because of a rule.
+    pub fn record_prune(&self, rule: Prune) {
+        let counter = match rule {
+            Prune::Size => &self.pruned_by_size,
+            Prune::Shape => &self.pruned_by_shape,
+            Prune::Pair => &self.pruned_by_pair,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Counts a solution found.
+    pub fn record_solution(&self) {
+        self.solutions.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Counts the branches a search is about to take from a board with `depth` pieces placed.
+    /// Only the top-level branches are counted, to measure progress by.
+    pub fn record_branches(&self, depth: usize, branches: usize) {
+        if depth == 0 {
+            self.branches.fetch_add(branches, Ordering::Relaxed);
+        }
+    }
+
+    /// Counts a branch searched to the end from a board with `depth` pieces placed.
+    pub fn record_branch_done(&self, depth: usize) {
+        if depth == 0 {
+            self.branches_done.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Gets the number of boards visited at any depth.
+    pub fn nodes(&self) -> usize {
+        self.nodes_by_depth
+            .iter()
+            .map(|counter| counter.load(Ordering::Relaxed))
+            .sum()
+    }
+
+    /// Gets the number of placements abandoned by any rule.
+    pub fn pruned(&self) -> usize {
+        [
+            &self.pruned_by_size,
+            &self.pruned_by_shape,
+            &self.pruned_by_pair,
+        ]
+        .iter()
+        .map(|counter| counter.load(Ordering::Relaxed))
+        .sum()
+    }
+
+    /// Gets, for each depth the search reached, the boards visited and the average number of
+    /// branches taken from each of them (the boards visited one level deeper, per board).
+    pub fn branching_by_depth(&self) -> Vec<(usize, f64)> {
+        let nodes: Vec<usize> = self
+            .nodes_by_depth
+            .iter()
+            .map(|counter| counter.load(Ordering::Relaxed))
+            .take_while(|&nodes| nodes > 0)
+            .collect();
+        nodes
+            .iter()
+            .enumerate()
+            .map(|(depth, &count)| {
+                let deeper = nodes.get(depth + 1).copied().unwrap_or(0);
+                (count, deeper as f64 / count as f64)
+            })
+            .collect()
+    }
+}
+
+/// Shows how far a search has got on stderr, updating one line in place, until dropped.
+/// The remaining time is estimated from how long the finished top-level branches took.
+pub struct ProgressReporter {
+    finished: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ProgressReporter {
+    /// Starts reporting progress, if stderr is a terminal that can show it.
+    pub fn spawn(stats: Arc<SearchStats>) -> Self {
+        let finished = Arc::new(AtomicBool::new(false));
+        let thread = std::io::stderr().is_terminal().then(|| {
+            let finished = finished.clone();
+            thread::spawn(move || {
+                let start = Instant::now();
+                while !finished.load(Ordering::Relaxed) {
+                    thread::sleep(Duration::from_millis(200));
+                    eprint!("\r\x1b[K{}", progress_line(&stats, start.elapsed()));
+                    let _ = std::io::stderr().flush();
+                }
+                eprint!("\r\x1b[K");
+            })
+        });
+        ProgressReporter { finished, thread }
+    }
+}
+
+impl Drop for ProgressReporter {
+    fn drop(&mut self) {
+        self.finished.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Describes the progress of a search that has been running for `elapsed`.
+fn progress_line(stats: &SearchStats, elapsed: Duration) -> String {
+    let branches = stats.branches.load(Ordering::Relaxed);
+    let done = stats.branches_done.load(Ordering::Relaxed);
+    let solutions = stats.solutions.load(Ordering::Relaxed);
+    let estimate = match done {
+        0 => "estimating time left".to_string(),
+        _ => {
+            let left = elapsed.as_secs_f64() * branches.saturating_sub(done) as f64 / done as f64;
+            format!("about {:.0}s left", left)
+        }
+    };
+    format!(
+        "{}/{} branches searched, {} solutions, {} nodes, {}",
+        done,
+        branches,
+        solutions,
+        stats.nodes(),
+        estimate
+    )
+}
@@ -0,0 +1,208 @@
+use crate::board::Board;
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// How many solved boards may wait in the channel before the search pauses.
+const CHANNEL_BOUND: usize = 64;
+
+/// The states of a search, as a `CancellationToken` keeps track of them.
+const RUNNING: u8 = 0;
+const FINISHED: u8 = 1;
+const CANCELLED: u8 = 2;
+const PANICKED: u8 = 3;
+
+/// Lets a running search be cancelled from another thread, or after a timeout.
+/// Cancelling a search that has already finished by itself does nothing, so a search
+/// only counts as cancelled if it was actually cut short. A search that panicked is
+/// neither finished nor cancelled, and its token tells so.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    state: Arc<AtomicU8>,
+    stop: Arc<AtomicBool>, // The flag the search watches
+}
+
+impl CancellationToken {
+    /// Tells the search to stop as soon as it can, unless it has already finished.
+    pub fn cancel(&self) {
+        if self
+            .state
+            .compare_exchange(RUNNING, CANCELLED, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok()
+        {
+            self.stop.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Cancels the search once `timeout` has passed, from a timer thread.
+    pub fn cancel_after(&self, timeout: Duration) {
+        let token = self.clone();
+        thread::spawn(move || {
+            thread::sleep(timeout);
+            token.cancel();
+        });
+    }
+
+    /// Checks whether the search was cancelled before it finished.
+    pub fn is_cancelled(&self) -> bool {
+        self.state.load(Ordering::SeqCst) == CANCELLED
+    }
+
+    /// Checks whether the search panicked, so the boards it gave could be incomplete.
+    pub fn has_panicked(&self) -> bool {
+        self.state.load(Ordering::SeqCst) == PANICKED
+    }
+
+    /// Records that the search finished by itself, so it can no longer be cancelled.
+    fn finish(&self) {
+        let _ = self
+            .state
+            .compare_exchange(RUNNING, FINISHED, Ordering::SeqCst, Ordering::SeqCst);
+    }
+}
+
+/// An iterator over solved boards, yielding each one as soon as the search finds it.
+/// The search runs on a background thread and is told to stop when the stream is dropped.
+pub struct SolutionStream {
+    receiver: Receiver<Board>,
+    token: CancellationToken,
+}
+
+impl SolutionStream {
+    /// Runs `search` on a background thread, streaming the boards it sends.
+    /// The search should return once the stop flag it is given has been set.
+    pub fn spawn<F>(search: F) -> Self
+    where
+        F: FnOnce(&SyncSender<Board>, &AtomicBool) + Send + 'static,
+    {
+        Self::spawn_with_token(move |solutions, token| {
+            search(solutions, &token.stop);
+            token.finish();
+        })
+    }
+
+    /// Runs `run` on a background thread, streaming the boards it sends.
+    /// It is responsible for calling `finish` on the token once its search is over.
+    fn spawn_with_token<F>(run: F) -> Self
+    where
+        F: FnOnce(&SyncSender<Board>, &CancellationToken) + Send + 'static,
+    {
+        let (sender, receiver) = sync_channel(CHANNEL_BOUND);
+        let token = CancellationToken::default();
+
+        let search_token = token.clone();
+        thread::spawn(move || {
+            // Record a panic before the sender is dropped, so the stream never ends
+            // without the token saying why.
+            if panic::catch_unwind(AssertUnwindSafe(|| run(&sender, &search_token))).is_err() {
+                search_token.state.store(PANICKED, Ordering::SeqCst);
+            }
+        });
+
+        SolutionStream { receiver, token }
+    }
+
+    /// Gets a token that cancels the search. Once the stream has ended, the token tells
+    /// whether it was cancelled, and so whether the boards it gave could be incomplete.
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.token.clone()
+    }
+
+    /// Like `spawn`, but holds every board back until the search is over, then streams
+    /// them in `serialize()` order. Unless `find_all` is used, only the first one is kept.
+    /// The search is expected to find every solution, regardless of `find_all`.
+    pub fn spawn_sorted<F>(search: F, find_all: bool) -> Self
+    where
+        F: FnOnce(&SyncSender<Board>, &AtomicBool) + Send + 'static,
+    {
+        Self::spawn_with_token(move |solutions, token| {
+            let mut boards: Vec<Board> = thread::scope(|scope| {
+                let (sender, receiver) = sync_channel(CHANNEL_BOUND);
+                scope.spawn(move || search(&sender, &token.stop));
+                receiver.into_iter().collect()
+            });
+
+            // Every board has been found, so a slow reader cannot make the search time out.
+            token.finish();
+
+            boards.sort_by_key(|b| b.serialize());
+            if !find_all {
+                boards.truncate(1);
+            }
+
+            for board in boards {
+                if solutions.send(board).is_err() {
+                    break;
+                }
+            }
+        })
+    }
+}
+
+impl Iterator for SolutionStream {
+    type Item = Board;
+
+    fn next(&mut self) -> Option<Board> {
+        // The channel closes once the search has finished.
+        self.receiver.recv().ok()
+    }
+}
+
+impl Drop for SolutionStream {
+    fn drop(&mut self) {
+        self.token.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn test_sorted_stream_finishes_before_it_is_read() {
+        let count = CHANNEL_BOUND * 2;
+        let stream = SolutionStream::spawn_sorted(
+            move |solutions, _| {
+                for _ in 0..count {
+                    solutions.send(Board::new(1, 1, '·')).unwrap();
+                }
+            },
+            true,
+        );
+
+        // Nothing is read until the search is over, and more boards are waiting than fit in the channel.
+        let token = stream.cancellation_token();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while token.state.load(Ordering::SeqCst) == RUNNING && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(1));
+        }
+        token.cancel();
+
+        assert!(!token.is_cancelled());
+        assert_eq!(stream.count(), count);
+    }
+
+    #[test]
+    fn test_stream_reports_a_panicked_search() {
+        for sorted in [false, true] {
+            let search = |solutions: &SyncSender<Board>, _: &AtomicBool| {
+                solutions.send(Board::new(1, 1, '·')).unwrap();
+                panic!("the search failed");
+            };
+            let stream = match sorted {
+                true => SolutionStream::spawn_sorted(search, true),
+                false => SolutionStream::spawn(search),
+            };
+
+            let token = stream.cancellation_token();
+            let _ = stream.count();
+            assert!(token.has_panicked());
+            assert!(!token.is_cancelled());
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use crate::{
+    board::Board,
+    calendar::Day,
+    piece::Piece,
+    puzzle::Puzzle,
+    solve_all::{all_days, solution_path},
+};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A problem with one solution in a solutions file.
+#[derive(Debug)]
+pub struct VerifyError {
+    /// 1-based line number in the file
+    pub line: usize,
+    /// What is wrong with the solution
+    pub message: String,
+}
+
+/// The outcome of verifying a tree of solution files.
+#[derive(Debug, Default)]
+pub struct VerifyReport {
+    /// Files checked
+    pub checked: usize,
+    /// Dates without a solutions file
+    pub missing: Vec<PathBuf>,
+    /// Files with problems
+    pub errors: Vec<(PathBuf, Vec<VerifyError>)>,
+}
+
+/// Verifies every `<Month>/<day>/<Weekday>.txt` file under `root` against a puzzle.
+/// It is an error for `root` not to be a directory, rather than every file being missing.
+pub fn verify_tree(root: &Path, puzzle: &Puzzle) -> io::Result<VerifyReport> {
+    if !root.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "no such solutions directory",
+        ));
+    }
+    let mut report = VerifyReport::default();
+
+    for day in all_days(&puzzle.layout) {
+        let path = solution_path(root, &day);
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                report.missing.push(path);
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+
+        report.checked += 1;
+        let errors = verify_solutions(puzzle, &day, &contents);
+        if !errors.is_empty() {
+            report.errors.push((path, errors));
+        }
+    }
+
+    Ok(report)
+}
+
+/// Verifies the serialized solutions for a day of a puzzle, one per line.
+pub fn verify_solutions(puzzle: &Puzzle, day: &Day, contents: &str) -> Vec<VerifyError> {
+    let board = match Board::make(&puzzle.layout, day) {
+        Ok(board) => board,
+        Err(e) => {
+            return vec![VerifyError {
+                line: 0,
+                message: format!("Failed to make the board for {}: {}", day, e),
+            }]
+        }
+    };
+    let pieces = puzzle.pieces_for_board(&board);
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    let mut errors = Vec::new();
+
+    for (i, line) in contents.lines().enumerate() {
+        let messages = match seen.insert(line, i + 1) {
+            Some(first) => vec![format!("Duplicate of the solution on line {}", first)],
+            None => verify_solution(&board, &pieces, line),
+        };
+        errors.extend(messages.into_iter().map(|message| VerifyError {
+            line: i + 1,
+            message,
+        }));
+    }
+
+    errors
+}
+
+/// Checks one serialized solution against the day's board and pieces.
+fn verify_solution(board: &Board, pieces: &[Piece], line: &str) -> Vec<String> {
+    let solved = match Board::deserialize(board.width, board.height, line, pieces) {
+        Ok(solved) => solved,
+        Err(e) => return vec![e.to_string()],
+    };
+
+    // The date and corner cells must be exactly where the board puts them,
+    // and every other cell must be covered by a piece.
+    let mut errors = Vec::new();
+    let is_fixed = |c: Option<char>| c.is_some_and(|c| c.is_ascii_lowercase());
+    for y in 0..board.height {
+        for x in 0..board.width {
+            let (wanted, found) = (board.get(x, y), solved.get(x, y));
+            match found {
+                None => errors.push(format!("Cell ({}, {}) is not covered", x, y)),
+                Some(found) if is_fixed(wanted) && Some(found) != wanted => errors.push(format!(
+                    "Expected '{}' at ({}, {}), found '{}'",
+                    wanted.unwrap(),
+                    x,
+                    y,
+                    found
+                )),
+                Some(found) if !is_fixed(wanted) && is_fixed(Some(found)) => {
+                    errors.push(format!("Unexpected '{}' at ({}, {})", found, x, y))
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    // Each piece must cover the cells of one of its allowed placements.
+    for piece in pieces {
+        match solved.mask_of_symbol(piece.symbol) {
+            0 => errors.push(format!("Piece {} is missing", piece.symbol)),
+            mask if !piece.get_allowed_masks().contains(&mask) => errors.push(format!(
+                "Piece {} at {} does not match any rotation or flip of the piece",
+                piece.symbol,
+                describe_cells(board, mask)
+            )),
+            _ => {}
+        }
+    }
+
+    errors
+}
+
+/// Lists the coordinates of the cells in a mask, like "(0, 0) (1, 0)".
+fn describe_cells(board: &Board, mask: u64) -> String {
+    board
+        .coordinates_of(mask)
+        .iter()
+        .map(|(x, y)| format!("({}, {})", x, y))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::march_3_tuesday;
+
+    #[test]
+    fn test_verify_tree_needs_a_directory() {
+        let missing = Path::new("solutions/no-such-puzzle");
+        let error = verify_tree(missing, &Puzzle::calendarium()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_verify_solutions() {
+        let known = march_3_tuesday();
+        let solution = &known.solutions[0];
+        // Swaps the month and day cells on the first row.
+        let misplaced: String = solution
+            .chars()
+            .map(|c| match c {
+                'm' => 'd',
+                'd' => 'm',
+                c => c,
+            })
+            .collect();
+        // Gives the F and Y pieces each other's cells, which do not have their shapes.
+        let swapped: String = solution
+            .chars()
+            .map(|c| match c {
+                'F' => 'Y',
+                'Y' => 'F',
+                c => c,
+            })
+            .collect();
+        let contents = [solution.as_str(), &misplaced, solution, &swapped].join("\n");
+
+        let errors: Vec<(usize, String)> =
+            verify_solutions(&Puzzle::calendarium(), &known.day, &contents)
+                .into_iter()
+                .map(|e| (e.line, e.message))
+                .collect();
+        let expected = [
+            (2, "Expected 'm' at (2, 0), found 'd'"),
+            (2, "Expected 'd' at (6, 0), found 'm'"),
+            (3, "Duplicate of the solution on line 1"),
+            (4, "Piece F at (0, 1) (0, 2) (0, 3) (1, 3) (0, 4) does not match any rotation or flip of the piece"),
+            (4, "Piece Y at (0, 0) (1, 0) (1, 1) (2, 1) (1, 2) does not match any rotation or flip of the piece"),
+        ];
+        let expected: Vec<(usize, String)> = expected
+            .iter()
+            .map(|&(line, message)| (line, message.to_string()))
+            .collect();
+        assert_eq!(errors, expected);
+    }
+}